@@ -0,0 +1,809 @@
+use crate::error::{GripError, Result};
+use crate::utils;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use tar::EntryType;
+
+/// Archive and compression formats grip can unpack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarBz2,
+    TarZst,
+    /// A single compressed file, usually a bare binary such as `tool-linux-amd64.gz`
+    Gz,
+    Xz,
+    Bz2,
+    Zst,
+}
+
+impl ArchiveKind {
+    /// Detect the format from a file name
+    pub fn detect(filename: &str) -> Option<Self> {
+        let name = filename.to_lowercase();
+
+        let kind = if name.ends_with(".zip") {
+            ArchiveKind::Zip
+        } else if name.ends_with(".tar") {
+            ArchiveKind::Tar
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            ArchiveKind::TarGz
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            ArchiveKind::TarXz
+        } else if name.ends_with(".tar.bz2") || name.ends_with(".tbz2") || name.ends_with(".tbz") {
+            ArchiveKind::TarBz2
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            ArchiveKind::TarZst
+        } else if name.ends_with(".gz") {
+            ArchiveKind::Gz
+        } else if name.ends_with(".xz") {
+            ArchiveKind::Xz
+        } else if name.ends_with(".bz2") {
+            ArchiveKind::Bz2
+        } else if name.ends_with(".zst") {
+            ArchiveKind::Zst
+        } else {
+            return None;
+        };
+
+        Some(kind)
+    }
+
+    /// Whether this compresses a single file rather than bundling several
+    pub fn is_single_file(self) -> bool {
+        matches!(self, ArchiveKind::Gz | ArchiveKind::Xz | ArchiveKind::Bz2 | ArchiveKind::Zst)
+    }
+}
+
+/// Upper bound on the number of entries in one archive
+const MAX_ENTRIES: u64 = 100_000;
+/// Upper bound on the total number of bytes written while extracting one archive
+const MAX_TOTAL_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+/// Zip entries claiming a higher compression ratio than this are treated as zip bombs
+const MAX_COMPRESSION_RATIO: u64 = 1000;
+/// Entries smaller than this are exempt from the ratio check, tiny files compress absurdly well
+const RATIO_CHECK_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+fn unsafe_archive(msg: String) -> anyhow::Error {
+    GripError::UnsafeArchive(msg).into()
+}
+
+/// Keeps track of how much an archive has written so far
+#[derive(Default)]
+struct Budget {
+    entries: u64,
+    bytes: u64,
+}
+
+impl Budget {
+    fn add_entry(&mut self) -> Result<()> {
+        self.entries += 1;
+        if self.entries > MAX_ENTRIES {
+            return Err(unsafe_archive(format!("more than {} entries", MAX_ENTRIES)));
+        }
+        Ok(())
+    }
+
+    /// Copy `reader` into `writer`, failing once the archive as a whole exceeds its size limit.
+    /// The declared entry size is never trusted, only the bytes actually produced.
+    fn copy<R: Read, W: std::io::Write>(&mut self, reader: R, writer: &mut W) -> Result<()> {
+        let remaining = MAX_TOTAL_SIZE - self.bytes;
+        let copied = std::io::copy(&mut reader.take(remaining + 1), writer)?;
+        if copied > remaining {
+            return Err(unsafe_archive(format!(
+                "extracted size exceeds {} bytes",
+                MAX_TOTAL_SIZE
+            )));
+        }
+        self.bytes += copied;
+        Ok(())
+    }
+}
+
+/// Turn an archive entry name into a path relative to the extraction root, rejecting
+/// anything that could escape it. Returns `None` for entries naming the root itself.
+fn sanitize_entry_path(name: &Path) -> Result<Option<PathBuf>> {
+    let mut clean = PathBuf::new();
+
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                return Err(unsafe_archive(format!("entry '{}' contains '..'", name.display())));
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(unsafe_archive(format!("entry '{}' has an absolute path", name.display())));
+            }
+        }
+    }
+
+    Ok(if clean.as_os_str().is_empty() { None } else { Some(clean) })
+}
+
+/// Make sure a symlink stored at `entry` (relative to the root) and pointing at `target` stays inside the root
+fn check_link_target(entry: &Path, target: &Path) -> Result<()> {
+    let mut depth: Vec<&std::ffi::OsStr> = entry
+        .parent()
+        .map(|p| p.iter().collect())
+        .unwrap_or_default();
+
+    for component in target.components() {
+        match component {
+            Component::Normal(part) => depth.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth.pop().is_none() {
+                    return Err(unsafe_archive(format!(
+                        "symlink '{}' points outside the target directory ({})",
+                        entry.display(),
+                        target.display()
+                    )));
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(unsafe_archive(format!(
+                    "symlink '{}' has an absolute target ({})",
+                    entry.display(),
+                    target.display()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Follow `relative` from `root` the way the OS would, through any symlinks, and check it never
+/// leaves `root`. Components that don't exist are resolved lexically.
+fn resolves_inside(root: &Path, relative: &Path) -> bool {
+    /// Queue a relative path's components, `None` standing for `..`
+    fn queue(pending: &mut Vec<Option<std::ffi::OsString>>, path: &Path) -> bool {
+        let mut parts = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => parts.push(Some(part.to_os_string())),
+                Component::ParentDir => parts.push(None),
+                Component::CurDir => {}
+                Component::RootDir | Component::Prefix(_) => return false,
+            }
+        }
+        pending.extend(parts.into_iter().rev());
+        true
+    }
+
+    let mut current = PathBuf::new();
+    let mut pending = Vec::new();
+    let mut hops = 0;
+    if !queue(&mut pending, relative) {
+        return false;
+    }
+
+    while let Some(part) = pending.pop() {
+        let Some(part) = part else {
+            if !current.pop() {
+                return false;
+            }
+            continue;
+        };
+
+        let path = root.join(&current).join(&part);
+        if !path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            current.push(part);
+            continue;
+        }
+
+        hops += 1;
+        let Ok(target) = std::fs::read_link(&path) else {
+            return false;
+        };
+        if hops > 40 || !queue(&mut pending, &target) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Once every entry is in place, make sure no symlink resolves outside the root. Checking each
+/// link on its own isn't enough: a chain like `d/y -> ..` and `x -> d/y/..` only escapes as a whole,
+/// and a later entry can change where an earlier link leads.
+fn check_links(target_dir: &Path, links: &[PathBuf]) -> Result<()> {
+    for link in links {
+        if !resolves_inside(target_dir, link) {
+            let _ = std::fs::remove_file(target_dir.join(link));
+            return Err(unsafe_archive(format!(
+                "symlink '{}' resolves outside the target directory",
+                link.display()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse paths that go through symlinks created by earlier entries, since those could
+/// redirect a write outside the root
+fn check_parents(target_dir: &Path, relative: &Path) -> Result<PathBuf> {
+    let mut current = target_dir.to_path_buf();
+
+    if let Some(parent) = relative.parent() {
+        for part in parent.iter() {
+            current.push(part);
+            if current.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+                return Err(unsafe_archive(format!(
+                    "entry '{}' is nested under a symlink",
+                    relative.display()
+                )));
+            }
+        }
+    }
+
+    Ok(current)
+}
+
+/// Resolve where an entry is written, creating its parent directories
+fn destination(target_dir: &Path, relative: &Path) -> Result<PathBuf> {
+    let parent = check_parents(target_dir, relative)?;
+    std::fs::create_dir_all(parent)?;
+
+    let dest = target_dir.join(relative);
+
+    // Replace rather than follow anything already at the destination
+    if dest.symlink_metadata().is_ok_and(|m| !m.is_dir()) {
+        std::fs::remove_file(&dest)?;
+    }
+
+    Ok(dest)
+}
+
+/// Apply the permission bits recorded in the archive, minus setuid, setgid and sticky
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(_path, std::fs::Permissions::from_mode(_mode & 0o777))?;
+    }
+    Ok(())
+}
+
+fn create_file(dest: &Path) -> Result<File> {
+    Ok(std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)?)
+}
+
+/// Extract `archive_path` into `target_dir`
+pub async fn extract(archive_path: &Path, target_dir: &Path) -> Result<()> {
+    let filename = archive_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let kind = ArchiveKind::detect(&filename)
+        .ok_or_else(|| GripError::InstallError(format!("Unsupported archive format: {}", filename)))?;
+
+    let archive_path = archive_path.to_path_buf();
+    let target_dir = target_dir.to_path_buf();
+
+    // Decompression is CPU bound and uses blocking IO, keep it off the async workers
+    tokio::task::spawn_blocking(move || extract_blocking(kind, &archive_path, &target_dir))
+        .await
+        .map_err(|e| GripError::InstallError(format!("Extraction of {} panicked: {}", filename, e)))?
+        .map_err(|e| {
+            // Security violations keep their own error so they are never mistaken for a corrupt download
+            if matches!(e.downcast_ref::<GripError>(), Some(GripError::UnsafeArchive(_))) {
+                e
+            } else {
+                GripError::InstallError(format!("Failed to extract {}: {}", filename, e)).into()
+            }
+        })
+}
+
+fn extract_blocking(kind: ArchiveKind, archive_path: &Path, target_dir: &Path) -> Result<()> {
+    let file = BufReader::new(File::open(archive_path)?);
+
+    match kind {
+        ArchiveKind::Zip => unpack_zip(file, target_dir)?,
+        ArchiveKind::Tar => unpack_tar(file, target_dir)?,
+        ArchiveKind::TarGz => unpack_tar(flate2::read::GzDecoder::new(file), target_dir)?,
+        ArchiveKind::TarXz => unpack_tar(xz2::read::XzDecoder::new(file), target_dir)?,
+        ArchiveKind::TarBz2 => unpack_tar(bzip2::read::BzDecoder::new(file), target_dir)?,
+        ArchiveKind::TarZst => unpack_tar(zstd::stream::read::Decoder::new(file)?, target_dir)?,
+        ArchiveKind::Gz => decompress_file(flate2::read::GzDecoder::new(file), archive_path, target_dir)?,
+        ArchiveKind::Xz => decompress_file(xz2::read::XzDecoder::new(file), archive_path, target_dir)?,
+        ArchiveKind::Bz2 => decompress_file(bzip2::read::BzDecoder::new(file), archive_path, target_dir)?,
+        ArchiveKind::Zst => decompress_file(zstd::stream::read::Decoder::new(file)?, archive_path, target_dir)?,
+    }
+
+    Ok(())
+}
+
+fn unpack_zip<R: Read + std::io::Seek>(reader: R, target_dir: &Path) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut budget = Budget::default();
+    let mut links = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        budget.add_entry()?;
+
+        let Some(relative) = sanitize_entry_path(Path::new(file.name()))? else {
+            continue;
+        };
+
+        if file.size() > RATIO_CHECK_THRESHOLD
+            && file.size() / file.compressed_size().max(1) > MAX_COMPRESSION_RATIO
+        {
+            return Err(unsafe_archive(format!(
+                "entry '{}' has a suspicious compression ratio",
+                relative.display()
+            )));
+        }
+
+        let mode = file.unix_mode();
+        let file_type = mode.map(|m| m & 0o170000);
+
+        if file.is_dir() {
+            let dest = destination(target_dir, &relative)?;
+            std::fs::create_dir_all(dest)?;
+        } else if file_type == Some(0o120000) {
+            let mut link_target = String::new();
+            file.read_to_string(&mut link_target)?;
+            let link_target = PathBuf::from(link_target);
+            check_link_target(&relative, &link_target)?;
+
+            let dest = destination(target_dir, &relative)?;
+            utils::create_symlink(&link_target, &dest)?;
+            links.push(relative);
+        } else if file_type.is_none_or(|t| t == 0o100000 || t == 0) {
+            let dest = destination(target_dir, &relative)?;
+            let mut output = create_file(&dest)?;
+            budget.copy(&mut file, &mut output)?;
+            if let Some(mode) = mode {
+                set_mode(&dest, mode)?;
+            }
+        } else {
+            return Err(unsafe_archive(format!(
+                "entry '{}' is a device or other special file",
+                relative.display()
+            )));
+        }
+    }
+
+    check_links(target_dir, &links)
+}
+
+fn unpack_tar<R: Read>(reader: R, target_dir: &Path) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    let mut budget = Budget::default();
+    let mut links = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        budget.add_entry()?;
+
+        let entry_path = entry.path()?.into_owned();
+        let Some(relative) = sanitize_entry_path(&entry_path)? else {
+            continue;
+        };
+
+        match entry.header().entry_type() {
+            EntryType::Directory => {
+                let dest = destination(target_dir, &relative)?;
+                std::fs::create_dir_all(dest)?;
+            }
+            EntryType::Regular | EntryType::Continuous => {
+                let mode = entry.header().mode()?;
+                let dest = destination(target_dir, &relative)?;
+                let mut output = create_file(&dest)?;
+                budget.copy(&mut entry, &mut output)?;
+                set_mode(&dest, mode)?;
+            }
+            EntryType::Symlink => {
+                let link_target = entry
+                    .link_name()?
+                    .ok_or_else(|| unsafe_archive(format!("symlink '{}' has no target", relative.display())))?
+                    .into_owned();
+                check_link_target(&relative, &link_target)?;
+
+                let dest = destination(target_dir, &relative)?;
+                utils::create_symlink(&link_target, &dest)?;
+                links.push(relative);
+            }
+            EntryType::Link => {
+                // Hard link targets are relative to the archive root rather than to the entry
+                let link_name = entry
+                    .link_name()?
+                    .ok_or_else(|| unsafe_archive(format!("hard link '{}' has no target", relative.display())))?
+                    .into_owned();
+                let source = sanitize_entry_path(&link_name)?
+                    .ok_or_else(|| unsafe_archive(format!("hard link '{}' points at the root", relative.display())))?;
+                check_parents(target_dir, &source)?;
+                let source = target_dir.join(source);
+                if source.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+                    return Err(unsafe_archive(format!(
+                        "hard link '{}' points at a symlink",
+                        relative.display()
+                    )));
+                }
+
+                let dest = destination(target_dir, &relative)?;
+                std::fs::hard_link(source, dest)?;
+            }
+            EntryType::XGlobalHeader | EntryType::XHeader | EntryType::GNULongName | EntryType::GNULongLink => {}
+            _ => {
+                return Err(unsafe_archive(format!(
+                    "entry '{}' is a device or other special file",
+                    relative.display()
+                )));
+            }
+        }
+    }
+
+    check_links(target_dir, &links)
+}
+
+/// Decompress a single-file stream next to the archive, dropping the compression extension
+fn decompress_file<R: Read>(reader: R, archive_path: &Path, target_dir: &Path) -> Result<()> {
+    let output_name = archive_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid archive name"))?;
+    let output_path = target_dir.join(output_name);
+
+    let mut output = File::create(&output_path)?;
+    Budget::default().copy(reader, &mut output)?;
+    utils::make_executable(&output_path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn detects_formats_from_names() {
+        assert_eq!(ArchiveKind::detect("tool-linux.tar.gz"), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::detect("TOOL.TGZ"), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::detect("tool.tar.xz"), Some(ArchiveKind::TarXz));
+        assert_eq!(ArchiveKind::detect("tool.tbz2"), Some(ArchiveKind::TarBz2));
+        assert_eq!(ArchiveKind::detect("tool.tar.zst"), Some(ArchiveKind::TarZst));
+        assert_eq!(ArchiveKind::detect("tool-linux-amd64.gz"), Some(ArchiveKind::Gz));
+        assert_eq!(ArchiveKind::detect("tool-linux-amd64"), None);
+        assert_eq!(ArchiveKind::detect("tool.exe"), None);
+
+        assert!(ArchiveKind::Xz.is_single_file());
+        assert!(!ArchiveKind::TarXz.is_single_file());
+    }
+
+    fn tool_tar() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(6);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, "tool-1.0/bin/tool", &b"binary"[..]).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    fn compress(kind: ArchiveKind, data: &[u8]) -> Vec<u8> {
+        match kind {
+            ArchiveKind::Tar => data.to_vec(),
+            ArchiveKind::TarGz | ArchiveKind::Gz => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            ArchiveKind::TarXz | ArchiveKind::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            ArchiveKind::TarBz2 | ArchiveKind::Bz2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            ArchiveKind::TarZst | ArchiveKind::Zst => zstd::stream::encode_all(data, 0).unwrap(),
+            ArchiveKind::Zip => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn extracts_every_tar_flavour() {
+        let tar = tool_tar();
+        for name in ["tool.tar", "tool.tar.gz", "tool.tar.xz", "tool.tar.bz2", "tool.tar.zst"] {
+            let dir = tempfile::tempdir().unwrap();
+            let archive = dir.path().join(name);
+            std::fs::write(&archive, compress(ArchiveKind::detect(name).unwrap(), &tar)).unwrap();
+
+            let target = dir.path().join("out");
+            std::fs::create_dir(&target).unwrap();
+            extract(&archive, &target).await.unwrap();
+
+            let tool = target.join("tool-1.0/bin/tool");
+            assert_eq!(std::fs::read(&tool).unwrap(), b"binary", "{}", name);
+            #[cfg(unix)]
+            assert!(utils::is_binary(&tool), "{} lost the executable bit", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn decompresses_single_files_without_the_extension() {
+        for name in ["tool-linux-amd64.gz", "tool-linux-amd64.xz", "tool-linux-amd64.bz2", "tool-linux-amd64.zst"] {
+            let dir = tempfile::tempdir().unwrap();
+            let archive = dir.path().join(name);
+            std::fs::write(&archive, compress(ArchiveKind::detect(name).unwrap(), b"binary")).unwrap();
+
+            extract(&archive, dir.path()).await.unwrap();
+
+            let tool = dir.path().join("tool-linux-amd64");
+            assert_eq!(std::fs::read(&tool).unwrap(), b"binary", "{}", name);
+            #[cfg(unix)]
+            assert!(utils::is_binary(&tool));
+        }
+    }
+
+    #[tokio::test]
+    async fn corrupt_archives_fail_with_the_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("tool.tar.xz");
+        std::fs::write(&archive, b"not xz at all").unwrap();
+
+        let err = extract(&archive, dir.path()).await.unwrap_err();
+        assert!(err.to_string().contains("tool.tar.xz"), "{}", err);
+    }
+
+    enum Entry<'a> {
+        File(&'a str, &'a [u8]),
+        Dir(&'a str),
+        Symlink(&'a str, &'a str),
+        HardLink(&'a str, &'a str),
+        Device(&'a str),
+    }
+
+    fn tar_archive(entries: &[Entry]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+
+        for entry in entries {
+            let mut header = tar::Header::new_gnu();
+            let (name, data): (&str, &[u8]) = match entry {
+                Entry::File(name, data) => {
+                    header.set_entry_type(EntryType::Regular);
+                    (name, data)
+                }
+                Entry::Dir(name) => {
+                    header.set_entry_type(EntryType::Directory);
+                    (name, b"")
+                }
+                Entry::Symlink(name, target) => {
+                    header.set_entry_type(EntryType::Symlink);
+                    header.set_link_name(target).unwrap();
+                    (name, b"")
+                }
+                Entry::HardLink(name, target) => {
+                    header.set_entry_type(EntryType::Link);
+                    header.set_link_name(target).unwrap();
+                    (name, b"")
+                }
+                Entry::Device(name) => {
+                    header.set_entry_type(EntryType::Char);
+                    (name, b"")
+                }
+            };
+
+            // set_path refuses hostile names, write the raw bytes so those can be tested too
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(data.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+
+        builder.into_inner().unwrap()
+    }
+
+    /// Unpack into `<tmp>/root`, so anything escaping by one level is still inside the temp dir
+    fn unpack(entries: &[Entry]) -> (tempfile::TempDir, PathBuf, Result<()>) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        let result = unpack_tar(tar_archive(entries).as_slice(), &root);
+        (dir, root, result)
+    }
+
+    fn is_unsafe(result: &Result<()>) -> bool {
+        result
+            .as_ref()
+            .err()
+            .is_some_and(|e| matches!(e.downcast_ref::<GripError>(), Some(GripError::UnsafeArchive(_))))
+    }
+
+    #[test]
+    fn symlink_chain_escaping_root_is_rejected() {
+        let (_dir, root, result) = unpack(&[
+            Entry::Dir("d"),
+            Entry::Symlink("d/y", ".."),
+            Entry::Symlink("x", "d/y/.."),
+        ]);
+        assert!(is_unsafe(&result));
+        assert!(!root.join("x").exists());
+    }
+
+    #[test]
+    fn symlink_redirected_by_later_entry_is_rejected() {
+        let (_dir, _root, result) = unpack(&[
+            Entry::Dir("d"),
+            Entry::Symlink("x", "d/y/.."),
+            Entry::Symlink("d/y", ".."),
+        ]);
+        assert!(is_unsafe(&result));
+    }
+
+    #[test]
+    fn symlink_chain_inside_root_is_allowed() {
+        let (_dir, root, result) = unpack(&[
+            Entry::Dir("bin"),
+            Entry::File("lib/tool", b"#!/bin/sh\n"),
+            Entry::Symlink("bin/current", "../lib"),
+            Entry::Symlink("bin/tool", "current/tool"),
+        ]);
+        result.unwrap();
+        assert_eq!(std::fs::read(root.join("bin/tool")).unwrap(), b"#!/bin/sh\n");
+    }
+
+    #[test]
+    fn sanitize_entry_path_rejects_escapes() {
+        assert_eq!(sanitize_entry_path(Path::new("./bin/tool")).unwrap(), Some(PathBuf::from("bin/tool")));
+        assert_eq!(sanitize_entry_path(Path::new("./")).unwrap(), None);
+        assert!(sanitize_entry_path(Path::new("../tool")).is_err());
+        assert!(sanitize_entry_path(Path::new("bin/../../tool")).is_err());
+        assert!(sanitize_entry_path(Path::new("/etc/passwd")).is_err());
+    }
+
+    #[test]
+    fn regular_entries_are_extracted() {
+        let (_dir, root, result) = unpack(&[
+            Entry::Dir("tool-1.0/"),
+            Entry::File("tool-1.0/bin/tool", b"binary"),
+            Entry::HardLink("tool-1.0/bin/alias", "tool-1.0/bin/tool"),
+        ]);
+        result.unwrap();
+        assert_eq!(std::fs::read(root.join("tool-1.0/bin/tool")).unwrap(), b"binary");
+        assert_eq!(std::fs::read(root.join("tool-1.0/bin/alias")).unwrap(), b"binary");
+    }
+
+    #[test]
+    fn traversal_entries_are_rejected() {
+        for name in ["../evil", "bin/../../evil", "/tmp/evil"] {
+            let (dir, _root, result) = unpack(&[Entry::File(name, b"evil")]);
+            assert!(is_unsafe(&result), "{} was accepted", name);
+            assert!(!dir.path().join("evil").exists());
+        }
+    }
+
+    #[test]
+    fn symlinks_pointing_outside_are_rejected() {
+        let (_dir, _root, result) = unpack(&[Entry::Symlink("link", "../outside")]);
+        assert!(is_unsafe(&result));
+
+        let (_dir, _root, result) = unpack(&[Entry::Symlink("bin/link", "../../outside")]);
+        assert!(is_unsafe(&result));
+
+        let (_dir, _root, result) = unpack(&[Entry::Symlink("link", "/etc/passwd")]);
+        assert!(is_unsafe(&result));
+    }
+
+    #[test]
+    fn writes_through_symlinks_are_rejected() {
+        let (dir, _root, result) = unpack(&[
+            Entry::Symlink("up", "."),
+            Entry::File("up/evil", b"evil"),
+        ]);
+        assert!(is_unsafe(&result));
+        assert!(!dir.path().join("evil").exists());
+    }
+
+    #[test]
+    fn hard_links_outside_or_to_symlinks_are_rejected() {
+        let (_dir, _root, result) = unpack(&[Entry::HardLink("link", "../outside")]);
+        assert!(is_unsafe(&result));
+
+        let (_dir, _root, result) = unpack(&[
+            Entry::Symlink("soft", "target"),
+            Entry::HardLink("hard", "soft"),
+        ]);
+        assert!(is_unsafe(&result));
+    }
+
+    #[test]
+    fn device_entries_are_rejected() {
+        let (_dir, root, result) = unpack(&[Entry::Device("dev/tty")]);
+        assert!(is_unsafe(&result));
+        assert!(!root.join("dev/tty").exists());
+    }
+
+    #[test]
+    fn budget_limits_entries_and_size() {
+        let mut budget = Budget { entries: MAX_ENTRIES, bytes: 0 };
+        assert!(budget.add_entry().is_err());
+
+        let mut budget = Budget { entries: 0, bytes: MAX_TOTAL_SIZE - 4 };
+        assert!(budget.copy(&b"1234"[..], &mut Vec::new()).is_ok());
+        assert!(budget.copy(&b"5"[..], &mut Vec::new()).is_err());
+    }
+
+    fn zip_archive(build: impl FnOnce(&mut zip::ZipWriter<std::io::Cursor<Vec<u8>>>)) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        build(&mut writer);
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn unpack_zip_bytes(bytes: Vec<u8>) -> (tempfile::TempDir, PathBuf, Result<()>) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        let result = unpack_zip(std::io::Cursor::new(bytes), &root);
+        (dir, root, result)
+    }
+
+    #[test]
+    fn zip_entries_are_extracted_with_modes() {
+        let options = zip::write::FileOptions::default().unix_permissions(0o4755);
+        let bytes = zip_archive(|zip| {
+            zip.start_file("bin/tool", options).unwrap();
+            std::io::Write::write_all(zip, b"binary").unwrap();
+        });
+
+        let (_dir, root, result) = unpack_zip_bytes(bytes);
+        result.unwrap();
+        assert_eq!(std::fs::read(root.join("bin/tool")).unwrap(), b"binary");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(root.join("bin/tool")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o7777, 0o755, "setuid must be dropped");
+        }
+    }
+
+    #[test]
+    fn zip_traversal_and_escaping_symlinks_are_rejected() {
+        let options = zip::write::FileOptions::default();
+        let bytes = zip_archive(|zip| {
+            zip.start_file("../evil", options).unwrap();
+            std::io::Write::write_all(zip, b"evil").unwrap();
+        });
+        let (dir, _root, result) = unpack_zip_bytes(bytes);
+        assert!(is_unsafe(&result));
+        assert!(!dir.path().join("evil").exists());
+
+        let bytes = zip_archive(|zip| {
+            zip.add_directory("d", options).unwrap();
+            zip.add_symlink("d/y", "..", options).unwrap();
+            zip.add_symlink("x", "d/y/..", options).unwrap();
+        });
+        let (_dir, _root, result) = unpack_zip_bytes(bytes);
+        assert!(is_unsafe(&result));
+    }
+
+    #[test]
+    fn zip_bombs_are_rejected() {
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Bzip2);
+        let bytes = zip_archive(|zip| {
+            zip.start_file("zeros", options).unwrap();
+            std::io::Write::write_all(zip, &vec![0u8; (RATIO_CHECK_THRESHOLD * 2) as usize]).unwrap();
+        });
+
+        let (_dir, root, result) = unpack_zip_bytes(bytes);
+        assert!(is_unsafe(&result));
+        assert!(!root.join("zeros").exists());
+    }
+}
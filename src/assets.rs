@@ -0,0 +1,375 @@
+use crate::utils;
+
+/// Outcome of matching release assets against the current platform
+#[derive(Debug)]
+pub enum AssetMatch {
+    /// A single best candidate (index into the asset list)
+    Best(usize),
+    /// Several candidates scored equally well, best first
+    Ambiguous(Vec<usize>),
+    /// Nothing looked installable for this platform
+    None,
+}
+
+const OS_TOKENS: &[(&str, &[&str])] = &[
+    ("linux", &["linux", "linux64", "linux32"]),
+    ("macos", &["darwin", "macos", "macosx", "mac", "osx", "apple"]),
+    ("windows", &["windows", "win", "win32", "win64", "msvc", "mingw"]),
+    ("freebsd", &["freebsd"]),
+    ("netbsd", &["netbsd"]),
+    ("openbsd", &["openbsd"]),
+    ("android", &["android"]),
+];
+
+/// File extensions that only say something about the OS when they end the name,
+/// so a tool called `deb-get` or `exe-wrapper` isn't taken for a system package
+const OS_EXTENSIONS: &[(&str, &[&str])] = &[
+    ("linux", &["appimage", "deb", "rpm"]),
+    ("macos", &["dmg", "pkg"]),
+    ("windows", &["exe", "msi"]),
+];
+
+const ARCH_TOKENS: &[(&str, &[&str])] = &[
+    ("x86_64", &["amd64", "x64", "64bit", "win64", "linux64"]),
+    ("aarch64", &["aarch64", "arm64", "armv8"]),
+    ("x86", &["i386", "i586", "i686", "386", "x86", "32bit", "win32", "linux32"]),
+    ("arm", &["arm", "armv6", "armv6l", "armv7", "armv7l", "armhf", "armel", "gnueabihf"]),
+    ("riscv64", &["riscv64", "riscv64gc"]),
+    ("powerpc64", &["ppc64", "ppc64le", "powerpc64", "powerpc64le"]),
+    ("s390x", &["s390x"]),
+    ("mips", &["mips", "mipsel", "mips64", "mips64el"]),
+];
+
+/// Suffixes of files that accompany a release but are never the thing to install
+const IGNORED_SUFFIXES: &[&str] = &[
+    ".sha256", ".sha256sum", ".sha512", ".sha512sum", ".sha1", ".md5", ".b3",
+    ".sig", ".asc", ".minisig", ".pem", ".crt", ".cert", ".bundle", ".sbom",
+    ".spdx", ".spdx.json", ".cdx.json", ".intoto.jsonl", ".json", ".txt",
+    ".pdb", ".dsym.zip", ".yml", ".yaml",
+];
+
+const IGNORED_TOKENS: &[&str] = &[
+    "checksums", "checksum", "sha256sums", "sha512sums", "shasums", "src", "source", "sources",
+    "debug", "symbols",
+];
+
+fn tokenize(name: &str) -> Vec<String> {
+    let lower = name
+        .to_lowercase()
+        .replace("x86_64", "amd64")
+        .replace("x86-64", "amd64")
+        .replace("32-bit", "32bit")
+        .replace("64-bit", "64bit");
+
+    lower
+        .split(['-', '_', '.', ' ', '+'])
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect()
+}
+
+fn detect<'a>(tokens: &[String], table: &'a [(&'a str, &'a [&'a str])]) -> Option<&'a str> {
+    table
+        .iter()
+        .find(|(_, aliases)| tokens.iter().any(|t| aliases.contains(&t.as_str())))
+        .map(|(key, _)| *key)
+}
+
+fn detect_os(name: &str, tokens: &[String]) -> Option<&'static str> {
+    detect(tokens, OS_TOKENS).or_else(|| {
+        let (_, extension) = name.rsplit_once('.')?;
+        let extension = extension.to_lowercase();
+        OS_EXTENSIONS
+            .iter()
+            .find(|(_, extensions)| extensions.contains(&extension.as_str()))
+            .map(|(key, _)| *key)
+    })
+}
+
+fn is_ignored(name: &str, tokens: &[String]) -> bool {
+    let lower = name.to_lowercase();
+    IGNORED_SUFFIXES.iter().any(|s| lower.ends_with(s))
+        || tokens.iter().any(|t| IGNORED_TOKENS.contains(&t.as_str()))
+}
+
+fn current_libc() -> &'static str {
+    #[cfg(target_env = "musl")]
+    return "musl";
+
+    #[cfg(not(target_env = "musl"))]
+    return "gnu";
+}
+
+/// Score an asset name for the given platform, or `None` if it must not be picked
+fn score_for(name: &str, os: &str, arch: &str, libc: &str) -> Option<i32> {
+    let tokens = tokenize(name);
+    if is_ignored(name, &tokens) {
+        return None;
+    }
+
+    let mut score = 0;
+
+    match detect_os(name, &tokens) {
+        Some(found) if found == os => score += 40,
+        Some(_) => return None,
+        None => {}
+    }
+
+    let universal = tokens.iter().any(|t| t == "universal" || t == "universal2");
+    match detect(&tokens, ARCH_TOKENS) {
+        Some(found) if found == arch => score += 30,
+        Some(_) => return None,
+        None if universal && os == "macos" => score += 25,
+        None => {}
+    }
+
+    if os == "linux" {
+        if tokens.iter().any(|t| t == libc) {
+            score += 6;
+        } else if tokens.iter().any(|t| t == "musl" || t == "static") {
+            // Statically linked builds run regardless of the host libc
+            score += 5;
+        } else if tokens.iter().any(|t| t == "gnu" || t == "glibc") {
+            score += 3;
+        }
+    }
+
+    let lower = name.to_lowercase();
+    score += if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") || lower.ends_with(".zip") {
+        10
+    } else if lower.ends_with(".tar.xz") || lower.ends_with(".tar.bz2") || lower.ends_with(".tar.zst") {
+        9
+    } else if lower.ends_with(".exe") {
+        8
+    } else if lower.ends_with(".gz") || lower.ends_with(".xz") {
+        6
+    } else if lower.ends_with(".appimage") {
+        4
+    } else if [".deb", ".rpm", ".apk", ".msi", ".dmg", ".pkg"].iter().any(|e| lower.ends_with(e)) {
+        // System packages need their own installer, so only use them as a last resort
+        -20
+    } else if !lower.contains('.') || lower.rsplit('.').next().is_some_and(|e| e.chars().any(|c| c.is_ascii_digit())) {
+        // Bare binary, possibly with a version number in the name
+        7
+    } else {
+        0
+    };
+
+    Some(score)
+}
+
+/// Rank asset names for the current platform, best first. Unusable assets are dropped.
+pub fn rank_assets(names: &[&str]) -> Vec<(usize, i32)> {
+    let os = utils::get_platform();
+    let arch = utils::get_arch();
+    let libc = current_libc();
+
+    let mut ranked: Vec<(usize, i32)> = names
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| score_for(name, os, arch, libc).map(|s| (i, s)))
+        .collect();
+
+    ranked.sort_by_key(|&(i, score)| (std::cmp::Reverse(score), i));
+    ranked
+}
+
+/// Pick the most suitable asset for the current platform
+pub fn match_asset(names: &[&str]) -> AssetMatch {
+    let ranked = rank_assets(names);
+
+    let Some(&(best, best_score)) = ranked.first() else {
+        return AssetMatch::None;
+    };
+
+    let ties: Vec<usize> = ranked
+        .iter()
+        .take_while(|(_, score)| *score == best_score)
+        .map(|(i, _)| *i)
+        .collect();
+
+    if ties.len() > 1 {
+        return AssetMatch::Ambiguous(ties);
+    }
+
+    // A lone candidate that says nothing about the platform is only a safe bet
+    // if it is also the only thing published
+    if best_score < 40 && ranked.len() > 1 {
+        return AssetMatch::Ambiguous(ranked.iter().map(|(i, _)| *i).collect());
+    }
+
+    AssetMatch::Best(best)
+}
+
+/// Find the asset in a newer release that corresponds to `previous`, the asset installed from `old_tag`
+pub fn match_previous(previous: &str, old_tag: &str, new_tag: &str, names: &[&str]) -> Option<usize> {
+    let old_version = old_tag.trim_start_matches('v');
+    let new_version = new_tag.trim_start_matches('v');
+
+    // Most projects only change the version number between releases
+    let expected = [
+        replace_version(previous, old_tag, new_tag),
+        replace_version(previous, old_version, new_version),
+    ];
+    if let Some(index) = names.iter().position(|name| expected.iter().any(|e| e == name)) {
+        return Some(index);
+    }
+
+    // Otherwise compare the names with every digit removed, which ignores version changes
+    // but keeps the platform and format parts intact
+    let shape = |name: &str| name.chars().filter(|c| !c.is_ascii_digit()).collect::<String>();
+    let previous_shape = shape(previous);
+    let matches: Vec<usize> = names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| shape(name) == previous_shape)
+        .map(|(i, _)| i)
+        .collect();
+
+    match matches.as_slice() {
+        [index] => Some(*index),
+        _ => None,
+    }
+}
+
+/// Replace `old` with `new` where it stands as a whole version number, so going from
+/// 6 to 7 turns `tool-6-x86_64` into `tool-7-x86_64` and not `tool-7-x87_74`
+fn replace_version(name: &str, old: &str, new: &str) -> String {
+    if old.is_empty() {
+        return name.to_string();
+    }
+
+    // A digit, or a dot that continues a number, means we are inside a longer number
+    let continues = |c: Option<char>, next: Option<char>| match c {
+        Some(c) if c.is_ascii_digit() => true,
+        Some('.') => next.is_some_and(|n| n.is_ascii_digit()),
+        _ => false,
+    };
+
+    let mut result = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(index) = rest.find(old) {
+        let mut before = rest[..index].chars().rev();
+        let mut after = rest[index + old.len()..].chars();
+        let (b1, b2) = (before.next(), before.next());
+        let (a1, a2) = (after.next(), after.next());
+
+        result.push_str(&rest[..index]);
+        if continues(b1, b2) || continues(a1, a2) {
+            result.push_str(old);
+        } else {
+            result.push_str(new);
+        }
+        rest = &rest[index + old.len()..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The asset `score_for` ranks highest for a platform, ties going to the first
+    fn best<'a>(names: &[&'a str], os: &str, arch: &str, libc: &str) -> Option<&'a str> {
+        names
+            .iter()
+            .filter_map(|name| score_for(name, os, arch, libc).map(|score| (name, score)))
+            .fold(None, |best: Option<(&&str, i32)>, (name, score)| match best {
+                Some((_, top)) if top >= score => best,
+                _ => Some((name, score)),
+            })
+            .map(|(name, _)| *name)
+    }
+
+    const RIPGREP: &[&str] = &[
+        "ripgrep-14.1.0-aarch64-apple-darwin.tar.gz",
+        "ripgrep-14.1.0-x86_64-apple-darwin.tar.gz",
+        "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip",
+        "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+        "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+        "ripgrep-14.1.0-i686-unknown-linux-gnu.tar.gz",
+        "ripgrep_14.1.0-1_amd64.deb",
+        "ripgrep-14.1.0.tar.gz",
+    ];
+
+    #[test]
+    fn picks_os_and_arch() {
+        assert_eq!(best(RIPGREP, "linux", "x86_64", "gnu"), Some("ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz"));
+        assert_eq!(best(RIPGREP, "macos", "aarch64", "gnu"), Some("ripgrep-14.1.0-aarch64-apple-darwin.tar.gz"));
+        assert_eq!(best(RIPGREP, "windows", "x86_64", "gnu"), Some("ripgrep-14.1.0-x86_64-pc-windows-msvc.zip"));
+    }
+
+    #[test]
+    fn prefers_the_host_libc() {
+        let names = ["tool-linux-amd64-gnu.tar.gz", "tool-linux-amd64-musl.tar.gz"];
+        assert_eq!(best(&names, "linux", "x86_64", "gnu"), Some("tool-linux-amd64-gnu.tar.gz"));
+        assert_eq!(best(&names, "linux", "x86_64", "musl"), Some("tool-linux-amd64-musl.tar.gz"));
+    }
+
+    #[test]
+    fn universal_macos_builds_match_any_arch() {
+        let names = ["tool-darwin-universal.tar.gz", "tool-linux-amd64.tar.gz"];
+        assert_eq!(best(&names, "macos", "aarch64", "gnu"), Some("tool-darwin-universal.tar.gz"));
+    }
+
+    #[test]
+    fn never_picks_checksums_signatures_or_other_platforms() {
+        for name in ["checksums.txt", "tool.tar.gz.sha256", "tool.tar.gz.sig", "tool-linux-amd64.tar.gz.pem", "sbom.spdx.json"] {
+            assert_eq!(score_for(name, "linux", "x86_64", "gnu"), None, "{}", name);
+        }
+        assert_eq!(score_for("tool-linux-arm64.tar.gz", "linux", "x86_64", "gnu"), None);
+        assert_eq!(score_for("tool-windows-amd64.zip", "linux", "x86_64", "gnu"), None);
+    }
+
+    #[test]
+    fn system_packages_are_a_last_resort() {
+        let names = ["tool_1.0_amd64.deb", "tool-linux-amd64"];
+        assert_eq!(best(&names, "linux", "x86_64", "gnu"), Some("tool-linux-amd64"));
+    }
+
+    #[test]
+    fn match_asset_without_candidates() {
+        assert!(matches!(match_asset(&["checksums.txt", "tool.sig"]), AssetMatch::None));
+        assert!(matches!(match_asset(&["tool"]), AssetMatch::Best(0)));
+    }
+
+    #[test]
+    fn platform_words_match_whole_tokens_only() {
+        // "mac" inside "machinectl", "win" inside "darwin"
+        assert!(score_for("machinectl-linux-amd64.tar.gz", "linux", "x86_64", "gnu").is_some());
+        assert!(score_for("tool-darwin-arm64.tar.gz", "macos", "aarch64", "gnu").is_some());
+        assert_eq!(score_for("tool-darwin-arm64.tar.gz", "windows", "aarch64", "gnu"), None);
+    }
+
+    #[test]
+    fn package_extensions_only_count_at_the_end() {
+        assert!(score_for("exe-wrapper-amd64.tar.gz", "linux", "x86_64", "gnu").is_some());
+        assert!(score_for("deb-get-amd64.tar.gz", "macos", "x86_64", "gnu").is_some());
+        assert!(score_for("pkg-tool-amd64.tar.gz", "windows", "x86_64", "gnu").is_some());
+        assert_eq!(score_for("tool-amd64.exe", "linux", "x86_64", "gnu"), None);
+        assert_eq!(score_for("tool_1.0_amd64.deb", "macos", "x86_64", "gnu"), None);
+        assert!(score_for("tool-amd64.exe", "windows", "x86_64", "gnu").is_some());
+    }
+
+    #[test]
+    fn match_previous_follows_version_bumps() {
+        let names = ["tool-1.3.0-linux-amd64.tar.gz", "tool-1.3.0-linux-arm64.tar.gz"];
+        assert_eq!(match_previous("tool-1.2.0-linux-arm64.tar.gz", "v1.2.0", "v1.3.0", &names), Some(1));
+
+        let names = ["tool_linux_amd64_build7.tar.gz", "tool_darwin_amd64_build7.tar.gz"];
+        assert_eq!(match_previous("tool_linux_amd64_build6.tar.gz", "r6", "r7", &names), Some(0));
+        assert_eq!(match_previous("tool-freebsd.tar.gz", "v1", "v2", &names), None);
+    }
+
+    #[test]
+    fn match_previous_only_replaces_the_version_token() {
+        assert_eq!(replace_version("tool-6-x86_64.tar.gz", "6", "7"), "tool-7-x86_64.tar.gz");
+        assert_eq!(replace_version("tool-1.2-arm64-1.2.tar.gz", "1.2", "1.3"), "tool-1.3-arm64-1.3.tar.gz");
+        assert_eq!(replace_version("tool-11.2-linux.tar.gz", "1.2", "1.3"), "tool-11.2-linux.tar.gz");
+
+        // Both the old and new release carry an x86_64 and an arm64 build
+        let names = ["tool-7-linux-arm64.tar.gz", "tool-7-linux-x86_64.tar.gz"];
+        assert_eq!(match_previous("tool-6-linux-x86_64.tar.gz", "v6", "v7", &names), Some(1));
+    }
+}
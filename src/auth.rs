@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+/// Find a GitHub token: `GITHUB_TOKEN`, then `GH_TOKEN`, then the grip config, then the gh CLI's login
+pub fn github_token(configured: Option<&str>) -> Option<String> {
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .chain(configured.map(str::to_string))
+        .chain(gh_cli_token())
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty())
+}
+
+fn gh_config_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("GH_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("gh"));
+    }
+
+    #[cfg(windows)]
+    return dirs::config_dir().map(|dir| dir.join("GitHub CLI"));
+
+    #[cfg(not(windows))]
+    return dirs::home_dir().map(|home| home.join(".config").join("gh"));
+}
+
+/// Read the github.com token from the gh CLI's `hosts.yml`. Only the simple layout gh writes is
+/// understood, a full YAML parser isn't worth it for one key:
+///
+/// ```yaml
+/// github.com:
+///     oauth_token: gho_xxx
+///     user: octocat
+/// ```
+fn gh_cli_token() -> Option<String> {
+    let content = std::fs::read_to_string(gh_config_dir()?.join("hosts.yml")).ok()?;
+    hosts_token(&content)
+}
+
+fn hosts_token(content: &str) -> Option<String> {
+    let mut in_github = false;
+    for line in content.lines() {
+        if !line.starts_with([' ', '\t']) {
+            in_github = line.trim_end().trim_end_matches(':') == "github.com";
+            continue;
+        }
+
+        if in_github {
+            if let Some(token) = line.trim().strip_prefix("oauth_token:") {
+                return Some(token.trim().trim_matches(['"', '\'']).to_string());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_github_com_token_from_gh_hosts() {
+        let hosts = "github.example.com:\n    oauth_token: ghe_other\ngithub.com:\n    user: octocat\n    oauth_token: \"gho_secret\"\n    git_protocol: https\n";
+        assert_eq!(hosts_token(hosts).as_deref(), Some("gho_secret"));
+    }
+
+    #[test]
+    fn ignores_other_hosts_and_empty_files() {
+        assert_eq!(hosts_token("github.example.com:\n    oauth_token: ghe_other\n"), None);
+        assert_eq!(hosts_token(""), None);
+    }
+}
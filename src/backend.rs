@@ -0,0 +1,458 @@
+use crate::archive;
+use crate::config::Registry;
+use crate::error::{GripError, Result};
+use crate::source::{GitRef, GitSource, RegistrySource};
+use colored::Colorize;
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Where a registry's package definitions come from. Every backend maintains a local copy with
+/// the definitions in `packages/<name>.json`, which is what lookups and search read.
+#[async_trait::async_trait]
+pub trait RegistryBackend: Send + Sync {
+    /// Create or refresh the local copy at `dest`
+    async fn sync(&self, dest: &Path, progress: &ProgressBar) -> Result<()>;
+}
+
+/// Pick the backend for a registry from its source
+pub fn for_registry(registry: &Registry, client: &reqwest::Client) -> Box<dyn RegistryBackend> {
+    match &registry.source {
+        RegistrySource::Git(source) => Box::new(GitBackend {
+            name: registry.name.clone(),
+            source: source.clone(),
+        }),
+        RegistrySource::Http(url) => Box::new(HttpBackend {
+            name: registry.name.clone(),
+            url: url.clone(),
+            client: client.clone(),
+        }),
+        RegistrySource::Local(path) => Box::new(LocalBackend {
+            name: registry.name.clone(),
+            path: path.clone(),
+        }),
+    }
+}
+
+/// A directory on disk, read in place rather than copied. Handy while writing package
+/// definitions and for vendored registries on machines without network access.
+pub struct LocalBackend {
+    name: String,
+    path: PathBuf,
+}
+
+#[async_trait::async_trait]
+impl RegistryBackend for LocalBackend {
+    /// Nothing to fetch, only check the directory is still there
+    async fn sync(&self, _dest: &Path, _progress: &ProgressBar) -> Result<()> {
+        if !self.path.join("packages").is_dir() {
+            return Err(GripError::RegistryError(format!(
+                "Registry {} has no packages directory in {}",
+                self.name,
+                self.path.display()
+            )).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A git repository with a `packages/` directory, at its root or in a subdirectory
+pub struct GitBackend {
+    name: String,
+    source: GitSource,
+}
+
+impl GitBackend {
+    async fn git(&self, action: &str, args: &[&str], dir: Option<&Path>) -> Result<()> {
+        let mut command = Command::new("git");
+        command.args(args);
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+        // Prompts can't be answered while several syncs share the terminal, rely on credential helpers
+        command.env("GIT_TERMINAL_PROMPT", "0");
+
+        let output = command.output().await.map_err(|e| {
+            GripError::RegistryError(format!("Failed to run git for registry {}: {}", self.name, e))
+        })?;
+        if !output.status.success() {
+            return Err(GripError::RegistryError(format!(
+                "Failed to {} registry {}: {}",
+                action,
+                self.name,
+                String::from_utf8_lossy(&output.stderr).lines().next().unwrap_or("").trim()
+            )).into());
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl RegistryBackend for GitBackend {
+    async fn sync(&self, dest: &Path, progress: &ProgressBar) -> Result<()> {
+        let url = self.source.clone_url();
+        let dest_arg = dest.to_string_lossy();
+
+        if !dest.exists() {
+            progress.set_message(format!("Cloning registry {}...", self.name.cyan()));
+            match &self.source.reference {
+                None => {
+                    self.git("clone", &["clone", "--quiet", "--depth", "1", &url, &dest_arg], None).await?;
+                }
+                Some(GitRef::Branch(branch)) => {
+                    self.git("clone", &["clone", "--quiet", "--depth", "1", "--branch", branch, &url, &dest_arg], None)
+                        .await?;
+                }
+                // A revision may be any commit, so it needs the full history
+                Some(GitRef::Rev(rev)) => {
+                    self.git("clone", &["clone", "--quiet", "--no-checkout", &url, &dest_arg], None).await?;
+                    self.git("check out", &["checkout", "--quiet", "--detach", rev], Some(dest)).await?;
+                }
+            }
+        } else {
+            progress.set_message(format!("Updating registry {}...", self.name.cyan()));
+            match &self.source.reference {
+                None | Some(GitRef::Branch(_)) => {
+                    self.git("update", &["pull", "--quiet", "--ff-only"], Some(dest)).await?;
+                }
+                Some(GitRef::Rev(rev)) => {
+                    self.git("update", &["fetch", "--quiet", "--tags", "origin"], Some(dest)).await?;
+                    self.git("check out", &["checkout", "--quiet", "--detach", rev], Some(dest)).await?;
+                }
+            }
+        }
+
+        let root = match &self.source.subdir {
+            Some(subdir) => dest.join(subdir),
+            None => dest.to_path_buf(),
+        };
+        if !root.join("packages").is_dir() {
+            return Err(GripError::RegistryError(format!(
+                "Registry {} has no packages directory{}",
+                self.name,
+                self.source.subdir.as_ref().map(|s| format!(" in {}", s)).unwrap_or_default()
+            )).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A single index file served over HTTP: either `index.json` holding every package definition,
+/// or an archive of the `packages/` directory. No git needed, any static file host will do.
+pub struct HttpBackend {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+/// Cache validators from the last download, kept in the local copy
+const VALIDATORS_FILE: &str = ".grip-http-cache.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl HttpBackend {
+    fn file_name(&self) -> String {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        path.rsplit('/').next().unwrap_or("index.json").to_string()
+    }
+
+    /// Write each definition of an `index.json` to `packages/<name>.json`. The index is either an
+    /// array of definitions or an object with a `packages` array.
+    fn unpack_index(&self, body: &[u8], packages_dir: &Path) -> Result<()> {
+        let index: serde_json::Value = serde_json::from_slice(body).map_err(|e| {
+            GripError::RegistryError(format!("Invalid index for registry {}: {}", self.name, e))
+        })?;
+
+        let packages = index
+            .as_array()
+            .or_else(|| index["packages"].as_array())
+            .ok_or_else(|| GripError::RegistryError(format!(
+                "Index for registry {} has no packages array",
+                self.name
+            )))?;
+
+        std::fs::create_dir_all(packages_dir)?;
+        for package in packages {
+            let name = package["name"].as_str().unwrap_or_default();
+            let valid = !name.is_empty()
+                && !name.starts_with('.')
+                && !name.contains(['/', '\\', ':']);
+            if !valid {
+                return Err(GripError::RegistryError(format!(
+                    "Invalid package name '{}' in index for registry {}",
+                    name, self.name
+                )).into());
+            }
+
+            std::fs::write(
+                packages_dir.join(format!("{}.json", name)),
+                serde_json::to_string_pretty(package)?,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Unpack an archive of package definitions and move its `packages/` directory to `packages_dir`
+    async fn unpack_archive(&self, body: &[u8], packages_dir: &Path) -> Result<()> {
+        let work = tempfile::tempdir()?;
+        let archive_path = work.path().join(self.file_name());
+        let extracted = work.path().join("extracted");
+        std::fs::write(&archive_path, body)?;
+        std::fs::create_dir_all(&extracted)?;
+        archive::extract(&archive_path, &extracted).await?;
+
+        let found = find_packages_dir(&extracted).ok_or_else(|| {
+            GripError::RegistryError(format!(
+                "Archive for registry {} contains no package definitions",
+                self.name
+            ))
+        })?;
+
+        // The temporary directory may live on another filesystem, so copy rather than rename
+        std::fs::create_dir_all(packages_dir)?;
+        for entry in std::fs::read_dir(&found)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+                std::fs::copy(&path, packages_dir.join(path.file_name().unwrap_or_default()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The directory holding the definitions in an unpacked archive: `packages/`, `<top>/packages/`,
+/// or the root itself when the archive was made from inside `packages/`
+fn find_packages_dir(root: &Path) -> Option<PathBuf> {
+    let has_definitions = |dir: &Path| {
+        std::fs::read_dir(dir).is_ok_and(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .any(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        })
+    };
+
+    if root.join("packages").is_dir() {
+        return Some(root.join("packages"));
+    }
+
+    let dirs: Vec<PathBuf> = std::fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    if let [top] = dirs.as_slice() {
+        if top.join("packages").is_dir() {
+            return Some(top.join("packages"));
+        }
+    }
+
+    has_definitions(root).then(|| root.to_path_buf())
+}
+
+#[async_trait::async_trait]
+impl RegistryBackend for HttpBackend {
+    async fn sync(&self, dest: &Path, progress: &ProgressBar) -> Result<()> {
+        progress.set_message(format!("Fetching registry {}...", self.name.cyan()));
+
+        let validators: Validators = std::fs::read_to_string(dest.join(VALIDATORS_FILE))
+            .ok()
+            .filter(|_| dest.join("packages").is_dir())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let mut request = self.client.get(&self.url).header("User-Agent", "grip");
+        if let Some(etag) = &validators.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            GripError::RegistryError(format!("Failed to fetch registry {}: {}", self.name, e))
+        })?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(());
+        }
+        if !response.status().is_success() {
+            return Err(GripError::RegistryError(format!(
+                "Failed to fetch registry {} ({})",
+                self.name,
+                response.status()
+            )).into());
+        }
+
+        let header = |name: reqwest::header::HeaderName| {
+            response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        };
+        let body = response.bytes().await?;
+
+        // Build the new copy next to the old one and swap them, so a broken index never
+        // replaces a working one
+        let staging = dest.with_file_name(format!(".{}.partial", dest.file_name().unwrap_or_default().to_string_lossy()));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(&staging)?;
+
+        let unpacked = if self.file_name().to_lowercase().ends_with(".json") {
+            self.unpack_index(&body, &staging.join("packages"))
+        } else {
+            self.unpack_archive(&body, &staging.join("packages")).await
+        };
+        if let Err(err) = unpacked {
+            std::fs::remove_dir_all(&staging)?;
+            return Err(err);
+        }
+
+        std::fs::write(staging.join(VALIDATORS_FILE), serde_json::to_string_pretty(&validators)?)?;
+        if dest.exists() {
+            std::fs::remove_dir_all(dest)?;
+        }
+        std::fs::rename(&staging, dest)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Response, Server};
+    use sha2::Digest;
+    use std::sync::{Arc, Mutex};
+
+    /// Stand-in for a static file host. Serves the current body with an ETag, answers 304 when
+    /// the client already has it, and records the status of every response.
+    struct Host {
+        server: Server,
+        body: Arc<Mutex<Vec<u8>>>,
+        statuses: Arc<Mutex<Vec<u16>>>,
+    }
+
+    impl Host {
+        async fn start(body: &[u8]) -> Self {
+            let body = Arc::new(Mutex::new(body.to_vec()));
+            let statuses = Arc::new(Mutex::new(Vec::new()));
+
+            let (served, recorded) = (body.clone(), statuses.clone());
+            let server = Server::start(move |request| {
+                let body = served.lock().unwrap().clone();
+                let etag = format!("\"{:x}\"", sha2::Sha256::digest(&body));
+                let status = if request.head.contains(&format!("if-none-match: {}", etag)) { 304 } else { 200 };
+                recorded.lock().unwrap().push(status);
+                let body = if status == 304 { Vec::new() } else { body };
+                Response::new(status, body).header("ETag", &etag)
+            })
+            .await;
+
+            Self { server, body, statuses }
+        }
+
+        fn backend(&self, file: &str) -> HttpBackend {
+            HttpBackend {
+                name: "test".to_string(),
+                url: self.server.url(&format!("/{}", file)),
+                client: reqwest::Client::new(),
+            }
+        }
+
+        fn serve(&self, body: &[u8]) {
+            *self.body.lock().unwrap() = body.to_vec();
+        }
+
+        fn statuses(&self) -> Vec<u16> {
+            self.statuses.lock().unwrap().clone()
+        }
+    }
+
+    fn index(names: &[&str]) -> Vec<u8> {
+        let packages: Vec<_> = names
+            .iter()
+            .map(|name| serde_json::json!({ "name": name, "repo": format!("owner/{}", name) }))
+            .collect();
+        serde_json::to_vec(&serde_json::json!({ "packages": packages })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn index_is_split_into_definitions_and_revalidated() {
+        let host = Host::start(&index(&["ripgrep", "fd"])).await;
+        let backend = host.backend("index.json");
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("registry");
+
+        backend.sync(&dest, &ProgressBar::hidden()).await.unwrap();
+        let definition: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dest.join("packages/ripgrep.json")).unwrap()).unwrap();
+        assert_eq!(definition["repo"], "owner/ripgrep");
+        assert!(dest.join("packages/fd.json").is_file());
+
+        // Unchanged: the server answers 304 and the local copy is left as it is
+        std::fs::remove_file(dest.join("packages/fd.json")).unwrap();
+        backend.sync(&dest, &ProgressBar::hidden()).await.unwrap();
+        assert_eq!(host.statuses(), [200, 304]);
+        assert!(!dest.join("packages/fd.json").exists());
+
+        // Changed: the new index replaces the old copy entirely
+        host.serve(&index(&["bat"]));
+        backend.sync(&dest, &ProgressBar::hidden()).await.unwrap();
+        assert_eq!(host.statuses(), [200, 304, 200]);
+        assert!(dest.join("packages/bat.json").is_file());
+        assert!(!dest.join("packages/ripgrep.json").exists());
+    }
+
+    #[tokio::test]
+    async fn broken_index_keeps_the_previous_copy() {
+        let host = Host::start(&index(&["ripgrep"])).await;
+        let backend = host.backend("index.json");
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("registry");
+        backend.sync(&dest, &ProgressBar::hidden()).await.unwrap();
+
+        for body in [index(&["../escape"]), index(&[".hidden"]), b"not json".to_vec(), b"{}".to_vec()] {
+            host.serve(&body);
+            assert!(backend.sync(&dest, &ProgressBar::hidden()).await.is_err());
+            assert!(dest.join("packages/ripgrep.json").is_file());
+            assert!(!dest.join("escape.json").exists());
+            assert!(!dir.path().join(".registry.partial").exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn archive_of_the_packages_directory() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        for (path, content) in [
+            ("registry-main/packages/ripgrep.json", r#"{ "name": "ripgrep", "repo": "BurntSushi/ripgrep" }"#),
+            ("registry-main/README.md", "# Registry"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+        }
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+
+        let host = Host::start(&archive).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("registry");
+        host.backend("registry.tar.gz").sync(&dest, &ProgressBar::hidden()).await.unwrap();
+
+        assert!(dest.join("packages/ripgrep.json").is_file());
+        assert!(!dest.join("packages/README.md").exists());
+    }
+}
@@ -0,0 +1,250 @@
+use sha2::{Digest, Sha256, Sha512};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha256,
+    Sha512,
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Algorithm::Sha256 => write!(f, "sha256"),
+            Algorithm::Sha512 => write!(f, "sha512"),
+        }
+    }
+}
+
+/// An expected digest for a downloaded file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: Algorithm,
+    pub hex: String,
+}
+
+impl Checksum {
+    /// Parse `sha256:<hex>`, `sha512:<hex>` or a bare hex digest, inferring the algorithm from its length
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (algorithm, hex) = match value.split_once(':') {
+            Some((prefix, hex)) => {
+                let algorithm = match prefix.to_lowercase().as_str() {
+                    "sha256" => Algorithm::Sha256,
+                    "sha512" => Algorithm::Sha512,
+                    _ => return None,
+                };
+                (Some(algorithm), hex)
+            }
+            None => (None, value),
+        };
+
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let by_length = match hex.len() {
+            64 => Algorithm::Sha256,
+            128 => Algorithm::Sha512,
+            _ => return None,
+        };
+        if algorithm.is_some_and(|a| a != by_length) {
+            return None;
+        }
+
+        Some(Self {
+            algorithm: by_length,
+            hex: hex.to_lowercase(),
+        })
+    }
+}
+
+impl std::fmt::Display for Checksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.hex)
+    }
+}
+
+/// Hashes a download as it streams in
+pub struct StreamHasher {
+    sha256: Sha256,
+    sha512: Option<Sha512>,
+}
+
+impl StreamHasher {
+    /// SHA-256 is always computed for the install record; SHA-512 only when something needs checking against it
+    pub fn new(expected: Option<&Checksum>) -> Self {
+        Self {
+            sha256: Sha256::new(),
+            sha512: expected
+                .filter(|c| c.algorithm == Algorithm::Sha512)
+                .map(|_| Sha512::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sha256.update(data);
+        if let Some(sha512) = &mut self.sha512 {
+            sha512.update(data);
+        }
+    }
+
+    /// Returns the SHA-256 digest and, if requested, the digest matching the expected algorithm
+    pub fn finalize(self, expected: Option<&Checksum>) -> (String, Option<String>) {
+        let sha256 = format!("{:x}", self.sha256.finalize());
+        let sha512 = self.sha512.map(|h| format!("{:x}", h.finalize()));
+
+        let actual = expected.map(|c| match c.algorithm {
+            Algorithm::Sha256 => sha256.clone(),
+            Algorithm::Sha512 => sha512.clone().unwrap_or_default(),
+        });
+
+        (sha256, actual)
+    }
+}
+
+/// Digest of a file on disk
+pub fn digest_file(path: &std::path::Path, algorithm: Algorithm) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    Ok(match algorithm {
+        Algorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            format!("{:x}", hasher.finalize())
+        }
+        Algorithm::Sha512 => {
+            let mut hasher = Sha512::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            format!("{:x}", hasher.finalize())
+        }
+    })
+}
+
+/// Release assets that may hold the checksum for `asset`, most specific first
+pub fn checksum_assets(asset: &str, names: &[&str]) -> Vec<usize> {
+    let per_asset: Vec<String> = ["sha256", "sha256sum", "sha512", "sha512sum"]
+        .iter()
+        .map(|ext| format!("{}.{}", asset, ext).to_lowercase())
+        .collect();
+
+    let mut candidates: Vec<usize> = names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| per_asset.contains(&name.to_lowercase()))
+        .map(|(i, _)| i)
+        .collect();
+
+    let signature_suffixes = [".sig", ".asc", ".minisig", ".pem", ".bundle"];
+    candidates.extend(
+        names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| {
+                let lower = name.to_lowercase();
+                (lower.contains("checksum") || lower.contains("sha256sum") || lower.contains("sha512sum")
+                    || lower.contains("shasum"))
+                    && !signature_suffixes.iter().any(|s| lower.ends_with(s))
+            })
+            .map(|(i, _)| i)
+            .filter(|i| !candidates.contains(i))
+            .collect::<Vec<_>>(),
+    );
+
+    candidates
+}
+
+/// Find the digest for `asset` in the contents of a checksum file. Understands the
+/// `sha256sum` output format, BSD style `SHA256 (file) = hex` lines and files holding a single digest.
+pub fn find_in_file(content: &str, asset: &str) -> Option<Checksum> {
+    let lines: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    for line in &lines {
+        // BSD style: SHA256 (file) = hex
+        if let Some((left, hex)) = line.split_once(") = ") {
+            if let Some((_, file)) = left.split_once(" (") {
+                if file_matches(file, asset) {
+                    return Checksum::parse(hex);
+                }
+            }
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(hex), Some(file)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if file_matches(file, asset) {
+            return Checksum::parse(hex);
+        }
+    }
+
+    // A per-asset file may contain nothing but the digest
+    match lines.as_slice() {
+        [single] if !single.contains(char::is_whitespace) => Checksum::parse(single),
+        _ => None,
+    }
+}
+
+fn file_matches(file: &str, asset: &str) -> bool {
+    let file = file.trim_start_matches('*').trim_start_matches("./");
+    file == asset || file.rsplit('/').next() == Some(asset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn parse_infers_and_checks_the_algorithm() {
+        let checksum = Checksum::parse(&SHA256.to_uppercase()).unwrap();
+        assert_eq!(checksum.algorithm, Algorithm::Sha256);
+        assert_eq!(checksum.hex, SHA256);
+
+        assert_eq!(Checksum::parse(&format!("sha256:{}", SHA256)), Some(checksum));
+        assert_eq!(Checksum::parse(&"ab".repeat(64)).unwrap().algorithm, Algorithm::Sha512);
+        assert_eq!(Checksum::parse(&format!("sha512:{}", SHA256)), None);
+        assert_eq!(Checksum::parse(&format!("md5:{}", SHA256)), None);
+        assert_eq!(Checksum::parse("not a digest"), None);
+        assert_eq!(Checksum::parse(&SHA256[..40]), None);
+    }
+
+    #[test]
+    fn find_in_sha256sum_output() {
+        let content = format!(
+            "# generated\n{}  tool-linux-amd64.tar.gz.sbom\n{} *tool-linux-amd64.tar.gz\n",
+            "0".repeat(64),
+            SHA256
+        );
+        assert_eq!(find_in_file(&content, "tool-linux-amd64.tar.gz").unwrap().hex, SHA256);
+        assert_eq!(find_in_file(&content, "tool-darwin-amd64.tar.gz"), None);
+    }
+
+    #[test]
+    fn find_with_directories_in_file_names() {
+        let content = format!("{}  ./dist/tool.zip\n", SHA256);
+        assert_eq!(find_in_file(&content, "tool.zip").unwrap().hex, SHA256);
+    }
+
+    #[test]
+    fn find_in_bsd_style_lines() {
+        let content = format!("SHA256 (tool.zip) = {}\n", SHA256);
+        assert_eq!(find_in_file(&content, "tool.zip").unwrap().hex, SHA256);
+        assert_eq!(find_in_file(&content, "other.zip"), None);
+    }
+
+    #[test]
+    fn find_in_single_digest_files() {
+        assert_eq!(find_in_file(&format!("{}\n", SHA256), "tool.zip").unwrap().hex, SHA256);
+    }
+
+    #[test]
+    fn checksum_assets_most_specific_first() {
+        let names = ["checksums.txt", "tool.zip", "tool.zip.sha256", "checksums.txt.sig", "other.zip.sha256"];
+        assert_eq!(checksum_assets("tool.zip", &names), vec![2, 0]);
+    }
+}
@@ -1,25 +1,136 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about = "GitHub Release Package Manager")]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Never prompt; use the latest stable release and the auto-matched asset
+    #[arg(short = 'y', long = "yes", visible_alias = "non-interactive", global = true)]
+    pub yes: bool,
+    /// Sync every registry before using it, ignoring cache_ttl
+    #[arg(long, global = true, conflicts_with = "offline")]
+    pub refresh: bool,
+    /// Use registries as last synced, without fetching them
+    #[arg(long, global = true)]
+    pub offline: bool,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum Commands {
-    /// Install a package
+    /// Install a package, or every dependency in grip.json when no package is given
     Install {
         /// Package name to install
-        package: String,
+        package: Option<String>,
         /// Specific version to install
-        #[arg(short, long)]
+        #[arg(short, long, requires = "package")]
         version: Option<String>,
         /// Specific asset to install
+        #[arg(short, long, requires = "package")]
+        asset: Option<String>,
+        /// Include prereleases
+        #[arg(long, requires = "package")]
+        pre: bool,
+        /// Install exactly what grip.lock records, failing if it disagrees with grip.json
+        #[arg(long, conflicts_with = "package")]
+        locked: bool,
+    },
+    /// Resolve project dependencies again and refresh grip.lock
+    Update {
+        /// Dependencies to update (all if omitted)
+        packages: Vec<String>,
+    },
+    /// Add a dependency to grip.json
+    Add {
+        /// Package name to add
+        package: String,
+        /// Version requirement (defaults to releases compatible with the latest)
+        #[arg(short, long)]
+        version: Option<String>,
+        /// Asset to install instead of the one matching the host platform
         #[arg(short, long)]
         asset: Option<String>,
     },
+    /// Remove a dependency from grip.json
+    Remove {
+        /// Package name to remove
+        package: String,
+    },
+    /// Uninstall a package
+    Uninstall {
+        /// Package name to uninstall
+        package: String,
+        /// Only remove this installed version
+        #[arg(short, long)]
+        version: Option<String>,
+    },
+    /// Upgrade installed packages to their latest release
+    Upgrade {
+        /// Packages to upgrade (all installed packages if omitted)
+        packages: Vec<String>,
+        /// Remove the previously installed versions afterwards
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Show installed packages that have a newer release
+    Outdated,
+    /// Run a package's binary without installing it globally
+    #[command(visible_alias = "run")]
+    Exec {
+        /// Package to run, optionally with a version requirement: name@version
+        package: String,
+        /// Binary to run when the package ships several
+        #[arg(short, long)]
+        bin: Option<String>,
+        /// Arguments passed to the binary, after `--` so grip's own flags are never taken from them
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Set the version of an installed package used wherever no version is pinned
+    Use {
+        /// name@version, or just a name to show the version pinned by .grip-version or grip.json
+        /// in this directory (all pinned packages if omitted)
+        package: Option<String>,
+    },
+    /// Run a binary through a shim in the bin directory, honouring versions pinned for the current directory
+    #[command(hide = true)]
+    Shim {
+        /// The binary to run when no pin applies
+        target: PathBuf,
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// List the installed versions of a package
+    Versions {
+        /// Package name
+        package: String,
+    },
+    /// Show details about a package and its latest release
+    Info {
+        /// Package name
+        package: String,
+        /// Inspect the release matching this version instead of the latest
+        #[arg(short, long)]
+        version: Option<String>,
+    },
+    /// Search the registries for packages
+    Search {
+        /// Words to look for in package names, tags and descriptions
+        query: Option<String>,
+        /// Only show packages with this tag (repeatable)
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List installed packages
+    List {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Initialize grip in current directory
     Init,
     /// Manage registries
@@ -35,7 +146,8 @@ pub enum RegistryCommands {
     Add {
         /// Registry name
         name: String,
-        /// Registry URL (github.com/owner/repo)
+        /// Registry URL: a git repository (github.com/owner/repo, git@host:path, https://...) with optional
+        /// //subdir and #branch or @rev, an index URL, or a local directory
         url: String,
         /// Priority (higher numbers are checked first)
         #[arg(short, long)]
@@ -48,4 +160,42 @@ pub enum RegistryCommands {
     },
     /// List configured registries
     List,
-}
\ No newline at end of file
+    /// Sync registries now, regardless of cache_ttl
+    Update {
+        /// Registry to update (all if omitted)
+        name: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yes_is_accepted_anywhere_and_aliased() {
+        for args in [
+            &["grip", "-y", "install", "ripgrep"][..],
+            &["grip", "install", "ripgrep", "--yes"],
+            &["grip", "install", "ripgrep", "--non-interactive"],
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            assert!(cli.yes, "{:?}", args);
+        }
+
+        assert!(!Cli::try_parse_from(["grip", "install", "ripgrep"]).unwrap().yes);
+    }
+
+    #[test]
+    fn run_hands_everything_after_dashes_to_the_tool() {
+        let cli = Cli::try_parse_from(["grip", "run", "protoc@25.1", "--", "--help", "-y", "api.proto"]).unwrap();
+        match cli.command {
+            Commands::Exec { package, bin, args } => {
+                assert_eq!(package, "protoc@25.1");
+                assert_eq!(bin, None);
+                assert_eq!(args, ["--help", "-y", "api.proto"]);
+            }
+            _ => panic!("expected exec"),
+        }
+        assert!(!cli.yes);
+    }
+}
@@ -1,11 +1,16 @@
+use colored::Colorize;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use crate::error::Result;
+use crate::signature::SignaturePolicy;
+use crate::source::{GitSource, RegistrySource};
+use crate::utils;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Registry {
     pub name: String,
-    pub url: String,
+    #[serde(rename = "url")]
+    pub source: RegistrySource,
     pub priority: i32,
 }
 
@@ -13,6 +18,15 @@ pub struct Registry {
 pub struct Settings {
     pub install_dir: String,
     pub auto_update: bool,
+    #[serde(default)]
+    pub signature_policy: SignaturePolicy,
+    /// How many registries are synced at once
+    #[serde(default = "default_sync_concurrency")]
+    pub sync_concurrency: usize,
+}
+
+fn default_sync_concurrency() -> usize {
+    4
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,6 +35,13 @@ pub struct Config {
     pub default_registry: String,
     pub cache_ttl: u64,
     pub settings: Settings,
+    /// Token for the GitHub API; `GITHUB_TOKEN` and `GH_TOKEN` take precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<String>,
+    /// Registry entries that failed to parse, kept as written so saving doesn't drop them and
+    /// `grip registry remove` can still find them
+    #[serde(skip)]
+    pub invalid_registries: Vec<serde_json::Value>,
 }
 
 impl Default for Config {
@@ -28,12 +49,14 @@ impl Default for Config {
         Self {
             registries: vec![Registry {
                 name: "default".to_string(),
-                url: "github.com/Grip-Packages/Grip-Packages".to_string(),
+                source: RegistrySource::Git(GitSource::new("github.com/Grip-Packages/Grip-Packages")),
                 priority: 100,
             }],
             default_registry: "github.com/Grip-Packages/Grip-Packages".to_string(),
             cache_ttl: 3600,
             settings: Settings::default(),
+            github_token: None,
+            invalid_registries: Vec::new(),
         }
     }
 }
@@ -43,10 +66,19 @@ impl Default for Settings {
         Self {
             install_dir: "$HOME/.local/bin".to_string(),
             auto_update: true,
+            signature_policy: SignaturePolicy::default(),
+            sync_concurrency: default_sync_concurrency(),
         }
     }
 }
 
+impl Settings {
+    /// Directory where installed binaries are exposed
+    pub fn bin_dir(&self) -> PathBuf {
+        PathBuf::from(utils::expand_path(&self.install_dir))
+    }
+}
+
 impl Config {
     pub fn load() -> Result<Self> {
         let config_path = dirs::config_dir()
@@ -56,7 +88,7 @@ impl Config {
 
         if config_path.exists() {
             let content = std::fs::read_to_string(config_path)?;
-            Ok(serde_json::from_str(&content)?)
+            Self::parse(&content)
         } else {
             let config = Config::default();
             std::fs::create_dir_all(config_path.parent().unwrap())?;
@@ -76,9 +108,83 @@ impl Config {
 
         std::fs::write(
             &config_path,
-            serde_json::to_string_pretty(&self)?,
+            self.to_json()?,
         )?;
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// The config as written to disk, unparsable registry entries included
+    fn to_json(&self) -> Result<String> {
+        let mut config = serde_json::to_value(self)?;
+        if let Some(serde_json::Value::Array(entries)) = config.get_mut("registries") {
+            entries.extend(self.invalid_registries.iter().cloned());
+        }
+        Ok(serde_json::to_string_pretty(&config)?)
+    }
+
+    /// Parse the config file. A registry entry that doesn't parse, say a mistyped URL, is
+    /// skipped with a warning rather than making every command fail.
+    fn parse(content: &str) -> Result<Self> {
+        let mut config: serde_json::Value = serde_json::from_str(content)?;
+        let entries = match config.get_mut("registries") {
+            Some(serde_json::Value::Array(entries)) => std::mem::take(entries),
+            _ => Vec::new(),
+        };
+
+        let mut registries = Vec::new();
+        let mut invalid_registries = Vec::new();
+        for entry in entries {
+            match serde_json::from_value::<Registry>(entry.clone()) {
+                Ok(registry) => registries.push(registry),
+                Err(err) => {
+                    let name = entry["name"].as_str().unwrap_or("<unnamed>");
+                    eprintln!(
+                        "{} Skipping registry {}: {} (fix it in the config or run `grip registry remove {}`)",
+                        "!".yellow(), name, err, name
+                    );
+                    invalid_registries.push(entry);
+                }
+            }
+        }
+
+        let mut config: Config = serde_json::from_value(config)?;
+        config.registries = registries;
+        config.invalid_registries = invalid_registries;
+        Ok(config)
+    }
+
+    /// Whether a registry of this name is configured, parsable or not
+    pub fn has_registry(&self, name: &str) -> bool {
+        self.registries.iter().any(|r| r.name == name)
+            || self.invalid_registries.iter().any(|entry| entry["name"] == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unparsable_registries_are_skipped_but_kept_on_save() {
+        let mut config = Config::default();
+        config.registries.push(Registry {
+            name: "extra".to_string(),
+            source: "github.com/example/extra".parse().unwrap(),
+            priority: 0,
+        });
+        let mut content: serde_json::Value = serde_json::from_str(&config.to_json().unwrap()).unwrap();
+        content["registries"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({ "name": "typo", "url": "github.com/example/typo//../..", "priority": 0 }));
+
+        let config = Config::parse(&content.to_string()).unwrap();
+        let names: Vec<&str> = config.registries.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["default", "extra"]);
+        assert!(config.has_registry("typo"));
+
+        let saved: serde_json::Value = serde_json::from_str(&config.to_json().unwrap()).unwrap();
+        assert_eq!(saved["registries"], content["registries"]);
+    }
+}
@@ -9,6 +9,11 @@ pub enum GripError {
     DownloadError(String),
     InstallError(String),
     RegistryError(String),
+    InteractionRequired(String),
+    UnsafeArchive(String),
+    ChecksumMismatch(String),
+    SignatureError(String),
+    RateLimited(String),
     IoError(std::io::Error),
     RequestError(reqwest::Error),
     Other(anyhow::Error),
@@ -24,6 +29,11 @@ impl std::fmt::Display for GripError {
             GripError::DownloadError(msg) => write!(f, "Download error: {}", msg),
             GripError::InstallError(msg) => write!(f, "Installation failed: {}", msg),
             GripError::RegistryError(msg) => write!(f, "Registry error: {}", msg),
+            GripError::RateLimited(msg) => write!(f, "GitHub API rate limit exceeded: {}", msg),
+            GripError::SignatureError(msg) => write!(f, "Signature verification failed: {}", msg),
+            GripError::ChecksumMismatch(msg) => write!(f, "Checksum mismatch for {}", msg),
+            GripError::UnsafeArchive(msg) => write!(f, "Refusing to extract unsafe archive: {}", msg),
+            GripError::InteractionRequired(msg) => write!(f, "Input required in non-interactive mode: {}", msg),
             GripError::IoError(e) => write!(f, "IO error: {}", e),
             GripError::RequestError(e) => write!(f, "Request error: {}", e),
             GripError::Other(e) => write!(f, "Error: {}", e),
@@ -58,4 +68,31 @@ impl From<anyhow::Error> for GripError {
     fn from(err: anyhow::Error) -> Self {
         GripError::Other(err)
     }
-}
\ No newline at end of file
+}
+
+/// Exit code for a failed command; scripts can tell "needs a human" apart from real failures
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    match err.downcast_ref::<GripError>() {
+        Some(GripError::InteractionRequired(_)) => 3,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interaction_required_has_its_own_exit_code() {
+        let err: anyhow::Error = GripError::InteractionRequired("Select asset".to_string()).into();
+        assert_eq!(exit_code(&err), 3);
+        // Clap exits with 2 on usage errors, so that code must stay free
+        assert_ne!(exit_code(&err), 2);
+    }
+
+    #[test]
+    fn other_errors_exit_with_one() {
+        assert_eq!(exit_code(&anyhow::anyhow!("boom")), 1);
+        assert_eq!(exit_code(&GripError::AssetNotFound("tool.zip".to_string()).into()), 1);
+    }
+}
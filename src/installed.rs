@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::error::Result;
+use crate::version::{self, Requirement};
+use std::path::{Path, PathBuf};
+
+/// A single installed version of a package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub registry: String,
+    pub repository: String,
+    pub tag: String,
+    pub asset: String,
+    pub download_url: String,
+    /// Digest of the downloaded asset; empty for records written before checksums were kept
+    #[serde(default)]
+    pub sha256: String,
+    pub installed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub binaries: Vec<String>,
+}
+
+/// Directory name a tag is installed under. Monorepo tags such as `cli/v0.4.0` contain
+/// slashes, so anything that isn't safe in a single path component becomes `_`.
+pub fn tag_dir(tag: &str) -> String {
+    let mut dir: String = tag
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "._+@-".contains(c) { c } else { '_' })
+        .collect();
+
+    // Keep `.`, `..` and hidden names like our own `.<tag>.partial` staging directories out
+    if dir.is_empty() || dir.starts_with('.') {
+        dir.insert(0, '_');
+    }
+    dir
+}
+
+/// Record of everything grip has installed, stored as `installed.json` in the data directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstalledDb {
+    pub packages: Vec<InstalledPackage>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl InstalledDb {
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join("installed.json");
+
+        let mut db = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            InstalledDb::default()
+        };
+
+        db.path = path;
+        Ok(db)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        // Write to a temporary file first so an interrupted save never leaves a truncated database
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(&self)?)?;
+        std::fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Add an installed version, replacing any previous record of the same tag
+    pub fn record(&mut self, package: InstalledPackage) {
+        self.packages
+            .retain(|p| !(p.name == package.name && p.tag == package.tag));
+        self.packages.push(package);
+    }
+
+    /// Forget a package, or only one of its versions
+    pub fn remove(&mut self, name: &str, tag: Option<&str>) {
+        self.packages
+            .retain(|p| !(p.name == name && tag.is_none_or(|t| p.tag == t)));
+    }
+
+    pub fn versions(&self, name: &str) -> Vec<&InstalledPackage> {
+        self.packages.iter().filter(|p| p.name == name).collect()
+    }
+
+    /// The most recently installed version of a package
+    pub fn latest(&self, name: &str) -> Option<&InstalledPackage> {
+        self.versions(name).into_iter().max_by_key(|p| p.installed_at)
+    }
+
+    /// The highest installed version satisfying `requirement`, the newest install breaking ties
+    pub fn newest_matching(&self, name: &str, requirement: &Requirement) -> Option<&InstalledPackage> {
+        self.versions(name)
+            .into_iter()
+            .filter(|p| requirement.matches(&p.tag))
+            .max_by_key(|p| (version::parse_tag(&p.tag), p.installed_at))
+    }
+
+    /// A version `grip exec` may run without asking GitHub: the highest one satisfying `requirement`,
+    /// or without one the most recent install while it is younger than `ttl`
+    pub fn cached(&self, name: &str, requirement: Option<&Requirement>, ttl: chrono::Duration) -> Option<&InstalledPackage> {
+        match requirement {
+            Some(requirement) => self.newest_matching(name, requirement),
+            None => self.latest(name).filter(|p| Utc::now() - p.installed_at < ttl),
+        }
+    }
+
+    /// Names of all installed packages, sorted and without duplicates
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.packages.iter().map(|p| p.name.clone()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Where packages are installed: the user-wide data directory, or a project's `.grip` directory
+pub struct Prefix {
+    pub root: PathBuf,
+    pub bin_dir: PathBuf,
+    /// Whether the bin directory belongs on the user's PATH
+    pub global: bool,
+}
+
+impl Prefix {
+    pub fn packages_dir(&self) -> PathBuf {
+        self.root.join("packages")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(name: &str, tag: &str) -> InstalledPackage {
+        InstalledPackage {
+            name: name.to_string(),
+            registry: "default".to_string(),
+            repository: format!("owner/{}", name),
+            tag: tag.to_string(),
+            asset: format!("{}-linux-amd64.tar.gz", name),
+            download_url: format!("https://example.com/{}/{}.tar.gz", name, tag),
+            sha256: String::new(),
+            installed_at: Utc::now(),
+            binaries: vec![name.to_string()],
+        }
+    }
+
+    #[test]
+    fn recording_a_tag_again_replaces_it() {
+        let mut db = InstalledDb::default();
+        db.record(installed("rg", "14.0.0"));
+        db.record(installed("rg", "14.1.0"));
+        db.record(installed("rg", "14.1.0"));
+        db.record(installed("fd", "9.0.0"));
+
+        let tags: Vec<&str> = db.versions("rg").iter().map(|p| p.tag.as_str()).collect();
+        assert_eq!(tags, ["14.0.0", "14.1.0"]);
+    }
+
+    #[test]
+    fn remove_one_version_or_all() {
+        let mut db = InstalledDb::default();
+        db.record(installed("rg", "14.0.0"));
+        db.record(installed("rg", "14.1.0"));
+        db.record(installed("fd", "9.0.0"));
+
+        db.remove("rg", Some("14.0.0"));
+        assert_eq!(db.versions("rg").len(), 1);
+
+        db.remove("rg", None);
+        assert!(db.versions("rg").is_empty());
+        assert_eq!(db.versions("fd").len(), 1);
+    }
+
+    #[test]
+    fn tags_become_single_path_components() {
+        assert_eq!(tag_dir("v14.1.0"), "v14.1.0");
+        assert_eq!(tag_dir("cli@1.2.0+build.5"), "cli@1.2.0+build.5");
+        assert_eq!(tag_dir("cli/v0.4.0"), "cli_v0.4.0");
+        assert_eq!(tag_dir("tools\\win 1"), "tools_win_1");
+        assert_eq!(tag_dir(".."), "_..");
+        assert_eq!(tag_dir("../../etc"), "_.._.._etc");
+        assert_eq!(tag_dir(""), "_");
+        for tag in ["cli/v0.4.0", "..", "../..", "/etc/passwd", ".hidden"] {
+            assert!(crate::utils::is_plain_component(&tag_dir(tag)), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn latest_is_the_most_recent_install() {
+        let mut db = InstalledDb::default();
+        let mut old = installed("rg", "14.1.0");
+        old.installed_at -= chrono::Duration::days(1);
+        db.record(installed("rg", "13.0.0"));
+        db.record(old);
+        db.record(installed("fd", "9.0.0"));
+
+        assert_eq!(db.latest("rg").unwrap().tag, "13.0.0");
+        assert!(db.latest("bat").is_none());
+        assert_eq!(db.names(), ["fd", "rg"]);
+    }
+
+    #[test]
+    fn cached_versions_for_exec() {
+        let mut db = InstalledDb::default();
+        let mut old = installed("rg", "14.1.0");
+        old.installed_at -= chrono::Duration::hours(2);
+        db.record(old);
+        db.record(installed("rg", "13.0.0"));
+
+        let hour = chrono::Duration::hours(1);
+        let requirement = Requirement::parse("14");
+        assert_eq!(db.cached("rg", Some(&requirement), hour).unwrap().tag, "14.1.0");
+        assert!(db.cached("rg", Some(&Requirement::parse("15")), hour).is_none());
+
+        // Without a version only a recent install counts, whatever its version
+        assert_eq!(db.cached("rg", None, hour).unwrap().tag, "13.0.0");
+        db.remove("rg", Some("13.0.0"));
+        assert!(db.cached("rg", None, hour).is_none());
+        assert!(db.cached("fd", None, hour).is_none());
+    }
+
+    #[test]
+    fn newest_matching_prefers_the_highest_version() {
+        let mut db = InstalledDb::default();
+        db.record(installed("terraform", "v1.5.7"));
+        db.record(installed("terraform", "v1.6.2"));
+        db.record(installed("terraform", "v1.5.2"));
+
+        let tag = |version: &str| db.newest_matching("terraform", &Requirement::parse(version)).map(|p| p.tag.as_str());
+        assert_eq!(tag("1.5"), Some("v1.5.7"));
+        assert_eq!(tag("latest"), Some("v1.6.2"));
+        assert_eq!(tag("v1.5.2"), Some("v1.5.2"));
+        assert_eq!(tag("1.7"), None);
+    }
+
+    #[test]
+    fn survives_a_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(InstalledDb::load(dir.path()).unwrap().packages.is_empty());
+
+        let mut db = InstalledDb::load(dir.path()).unwrap();
+        db.record(installed("rg", "14.1.0"));
+        db.save().unwrap();
+
+        let db = InstalledDb::load(dir.path()).unwrap();
+        assert_eq!(db.versions("rg")[0].download_url, "https://example.com/rg/14.1.0.tar.gz");
+        assert!(!dir.path().join("installed.json.tmp").exists());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::error::Result;
+use crate::installed::InstalledPackage;
+use crate::manifest::Manifest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const LOCK_FILE: &str = "grip.lock";
+
+/// The exact artifact a project dependency resolved to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    /// The requirement from `grip.json` this was resolved from
+    pub requirement: String,
+    pub registry: String,
+    pub repository: String,
+    pub tag: String,
+    pub asset: String,
+    pub download_url: String,
+    pub sha256: String,
+}
+
+impl LockedPackage {
+    pub fn new(requirement: &str, installed: &InstalledPackage) -> Self {
+        Self {
+            name: installed.name.clone(),
+            requirement: requirement.to_string(),
+            registry: installed.registry.clone(),
+            repository: installed.repository.clone(),
+            tag: installed.tag.clone(),
+            asset: installed.asset.clone(),
+            download_url: installed.download_url.clone(),
+            sha256: installed.sha256.clone(),
+        }
+    }
+}
+
+/// `grip.lock`, pinning every dependency of a project to one release asset
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    pub packages: Vec<LockedPackage>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Lockfile {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            version: 1,
+            packages: Vec::new(),
+            path: dir.join(LOCK_FILE),
+        }
+    }
+
+    /// Load the lockfile next to a manifest, if there is one
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let mut lock: Lockfile = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+        lock.path = path;
+        Ok(Some(lock))
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        // Keep the file stable so it diffs cleanly
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let content = serde_json::to_string_pretty(&self)?;
+        std::fs::write(&self.path, content + "\n")?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// Ways in which the lock no longer describes the manifest's dependencies
+    pub fn mismatches(&self, manifest: &Manifest) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (name, dependency) in &manifest.dependencies {
+            let Some(locked) = self.get(name) else {
+                problems.push(format!("{} is not locked", name));
+                continue;
+            };
+            if locked.requirement != dependency.version() {
+                problems.push(format!(
+                    "{} requires {} but was locked for {}",
+                    name,
+                    dependency.version(),
+                    locked.requirement
+                ));
+            }
+            if let Some(asset) = dependency.asset().filter(|asset| *asset != locked.asset) {
+                problems.push(format!("{} asks for asset {} but {} is locked", name, asset, locked.asset));
+            }
+            if let Some(registry) = dependency.registry().filter(|registry| *registry != locked.registry) {
+                problems.push(format!(
+                    "{} comes from registry {} but was locked from {}",
+                    name, registry, locked.registry
+                ));
+            }
+        }
+
+        for locked in &self.packages {
+            if !manifest.dependencies.contains_key(&locked.name) {
+                problems.push(format!("{} is locked but no longer a dependency", locked.name));
+            }
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{Dependency, DependencyDetails};
+
+    fn locked(name: &str, requirement: &str) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            requirement: requirement.to_string(),
+            registry: "default".to_string(),
+            repository: format!("owner/{}", name),
+            tag: "v1.0.0".to_string(),
+            asset: format!("{}-linux.tar.gz", name),
+            download_url: format!("https://example.com/{}-linux.tar.gz", name),
+            sha256: "00".repeat(32),
+        }
+    }
+
+    #[test]
+    fn a_lock_matching_the_manifest_has_no_mismatches() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manifest = Manifest::new(dir.path());
+        manifest.dependencies.insert("rg".to_string(), Dependency::Version("^14".to_string()));
+
+        let mut lock = Lockfile::new(dir.path());
+        lock.packages.push(locked("rg", "^14"));
+        assert!(lock.mismatches(&manifest).is_empty());
+    }
+
+    #[test]
+    fn changed_requirements_assets_and_registries_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manifest = Manifest::new(dir.path());
+        manifest.dependencies.insert("rg".to_string(), Dependency::Version("^15".to_string()));
+        manifest.dependencies.insert("fd".to_string(), Dependency::Detailed(DependencyDetails {
+            version: "^1".to_string(),
+            asset: Some("fd-musl.tar.gz".to_string()),
+            registry: Some("work".to_string()),
+        }));
+        manifest.dependencies.insert("bat".to_string(), Dependency::Version("latest".to_string()));
+
+        let mut lock = Lockfile::new(dir.path());
+        lock.packages.push(locked("rg", "^14"));
+        lock.packages.push(locked("fd", "^1"));
+        lock.packages.push(locked("jq", "latest"));
+
+        let problems = lock.mismatches(&manifest);
+        assert_eq!(problems, [
+            "bat is not locked",
+            "fd asks for asset fd-musl.tar.gz but fd-linux.tar.gz is locked",
+            "fd comes from registry work but was locked from default",
+            "rg requires ^15 but was locked for ^14",
+            "jq is locked but no longer a dependency",
+        ]);
+    }
+
+    #[test]
+    fn saved_sorted_and_loaded_back() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Lockfile::load(dir.path()).unwrap().is_none());
+
+        let mut lock = Lockfile::new(dir.path());
+        lock.packages.push(locked("rg", "^14"));
+        lock.packages.push(locked("fd", "^9"));
+        lock.save().unwrap();
+
+        let lock = Lockfile::load(dir.path()).unwrap().unwrap();
+        let names: Vec<&str> = lock.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["fd", "rg"]);
+        assert_eq!(lock.get("rg"), Some(&locked("rg", "^14")));
+    }
+}
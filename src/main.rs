@@ -1,26 +1,77 @@
+/// `println!` for progress messages, which go to stderr instead while `grip exec` is running a tool
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::utils::status_on_stderr() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+mod archive;
+mod auth;
+mod backend;
+mod assets;
+mod checksum;
 mod cli;
 mod config;
 mod error;
+mod installed;
+mod lockfile;
+mod manifest;
 mod package;
 mod path;
 mod registry;
+mod search;
+mod signature;
+mod source;
+#[cfg(test)]
+mod testing;
 mod utils;
+mod version;
 
+use archive::ArchiveKind;
+use assets::AssetMatch;
+use checksum::Checksum;
 use cli::{Cli, Commands, RegistryCommands};
 use colored::Colorize;
 use config::Config;
-use error::Result;
-use registry::RegistryManager;
+use error::{GripError, Result};
+use installed::{InstalledDb, InstalledPackage, Prefix};
+use lockfile::{LockedPackage, Lockfile};
+use manifest::{Dependency, Manifest};
+use package::Package;
+use registry::{DownloadedAsset, RegistryManager, SyncPolicy};
+use signature::{SignaturePolicy, Verification};
+use source::RegistrySource;
+use version::Requirement;
 use clap::Parser;
-use dialoguer::Select;
+use dialoguer::{Confirm, Select};
+use std::io::IsTerminal;
+use std::path::{Component, Path};
+
+/// A pending move of an installed package to a newer release
+struct Upgrade {
+    current: InstalledPackage,
+    package: Package,
+    release: serde_json::Value,
+}
+
+impl Upgrade {
+    fn new_tag(&self) -> &str {
+        self.release["tag_name"].as_str().unwrap_or("unknown")
+    }
+}
 
 struct Grip {
     config: Config,
     registry_manager: RegistryManager,
+    interactive: bool,
 }
 
 impl Grip {
-    async fn new() -> Result<Self> {
+    async fn new(interactive: bool, sync: Option<SyncPolicy>) -> Result<Self> {
         let data_dir = dirs::data_local_dir()
             .ok_or_else(|| anyhow::anyhow!("Failed to get local data directory"))?
             .join("grip");
@@ -28,15 +79,44 @@ impl Grip {
         std::fs::create_dir_all(&data_dir)?;
 
         let config = Config::load()?;
-        let registry_manager = RegistryManager::new(data_dir);
+        let token = auth::github_token(config.github_token.as_deref());
+        let sync = sync.unwrap_or(if config.settings.auto_update {
+            SyncPolicy::Auto { ttl: config.cache_ttl }
+        } else {
+            SyncPolicy::Manual
+        });
+        let registry_manager = RegistryManager::new(data_dir, token, sync, config.settings.sync_concurrency);
 
         Ok(Self {
             config,
             registry_manager,
+            interactive,
         })
     }
 
-    async fn install(&self, package_name: &str, version: Option<String>, asset: Option<String>) -> Result<()> {
+    /// The user-wide install location, whose bin directory is put on PATH
+    fn global_prefix(&self) -> Prefix {
+        Prefix {
+            root: self.registry_manager.data_dir.clone(),
+            bin_dir: self.config.settings.bin_dir(),
+            global: true,
+        }
+    }
+
+    /// Ask the user to pick one of `items`, or fail if nobody is there to answer
+    fn select(&self, prompt: &str, items: &[&str], default: usize, hint: &str) -> Result<usize> {
+        if !self.interactive {
+            return Err(GripError::InteractionRequired(format!("{} ({})", prompt, hint)).into());
+        }
+
+        Ok(Select::new()
+            .with_prompt(prompt)
+            .items(items)
+            .default(default)
+            .interact()?)
+    }
+
+    async fn install(&self, package_name: &str, version: Option<String>, asset: Option<String>, pre: bool) -> Result<()> {
         println!("{} Looking up package {}", "→".blue(), package_name.cyan());
 
         // Find package in registry
@@ -44,37 +124,68 @@ impl Grip {
         
         println!("{} Found package in repository: {}", "→".blue(), package.info.repository.cyan());
 
-        // Get releases from GitHub
-        let releases = self.registry_manager.get_releases(&package.info.repository).await?;
-
-        if releases.is_empty() {
-            anyhow::bail!("No releases found for package '{}'", package_name);
-        }
+        let repo = &package.info.repository;
 
         // Select release version
         let release = match version {
-            Some(ref v) => releases
-                .iter()
-                .find(|r| r["tag_name"].as_str().unwrap_or("") == v)
-                .ok_or_else(|| anyhow::anyhow!("Version {} not found", v))?,
+            Some(ref v) => self.resolve_requirement(&package, v, pre).await?,
+            None if !self.interactive => self.latest_release(&package, pre).await?.ok_or_else(|| {
+                GripError::InteractionRequired(format!(
+                    "no {}release of '{}' found (pass --version)",
+                    if pre { "" } else { "stable " },
+                    package_name
+                ))
+            })?,
             None => {
-                let versions: Vec<&str> = releases
-                    .iter()
-                    .filter_map(|r| r["tag_name"].as_str())
-                    .collect();
+                // Releases are fetched a page at a time; older ones only when asked for
+                let mut releases = Vec::new();
+                let mut next = Some(self.registry_manager.releases_url(repo, 30));
+                loop {
+                    if let Some(url) = next.take() {
+                        let (page, more) = self.registry_manager.get_releases_page(repo, &url, pre).await?;
+                        releases.extend(page);
+                        next = more;
+                        version::retain_tool(&mut releases, package.info.tag_prefix.as_deref());
+                        version::sort_releases(&mut releases);
+                    }
+                    if releases.is_empty() {
+                        if next.is_some() {
+                            continue;
+                        }
+                        anyhow::bail!("No releases found for package '{}'", package_name);
+                    }
+
+                    let mut versions: Vec<&str> = releases
+                        .iter()
+                        .map(|r| r["tag_name"].as_str().unwrap_or("unknown"))
+                        .collect();
+                    if next.is_some() {
+                        versions.push("Show older releases…");
+                    }
 
-                println!("{} Available versions:", "→".blue());
-                let selection = Select::new()
-                    .with_prompt("Select version")
-                    .items(&versions)
-                    .default(0)
-                    .interact()?;
+                    println!("{} Available versions:", "→".blue());
+                    let default = registry::latest_stable(&releases).unwrap_or(0);
+                    let selection = self.select("Select version", &versions, default, "pass --version")?;
 
-                &releases[selection]
+                    if selection < releases.len() {
+                        break releases.swap_remove(selection);
+                    }
+                }
             }
         };
+        let release = &release;
+
+        let asset_obj = self.select_asset(release, asset)?;
+
+        self.install_release(package_name, &package, release, asset_obj, &self.global_prefix(), None)
+            .await?;
 
-        // Select asset
+        println!("{} Installation complete!", "✓".green());
+        Ok(())
+    }
+
+    /// Pick the asset to install from a release: the one named explicitly, or the best platform match
+    fn select_asset<'a>(&self, release: &'a serde_json::Value, asset: Option<String>) -> Result<&'a serde_json::Value> {
         let assets = release["assets"]
             .as_array()
             .ok_or_else(|| anyhow::anyhow!("No assets found in release"))?;
@@ -83,24 +194,69 @@ impl Grip {
             Some(ref a) => assets
                 .iter()
                 .find(|asset| asset["name"].as_str().unwrap_or("") == a)
-                .ok_or_else(|| anyhow::anyhow!("Asset {} not found", a))?,
+                .ok_or_else(|| GripError::AssetNotFound(a.clone()))?,
             None => {
                 let asset_names: Vec<&str> = assets
                     .iter()
-                    .filter_map(|a| a["name"].as_str())
+                    .map(|a| a["name"].as_str().unwrap_or(""))
                     .collect();
 
-                println!("{} Available assets:", "→".blue());
-                let selection = Select::new()
-                    .with_prompt("Select asset")
-                    .items(&asset_names)
-                    .default(0)
-                    .interact()?;
+                match assets::match_asset(&asset_names) {
+                    AssetMatch::Best(index) => {
+                        status!(
+                            "{} Selected asset {} for {}-{}",
+                            "→".blue(),
+                            asset_names[index].cyan(),
+                            utils::get_platform(),
+                            utils::get_arch()
+                        );
+                        &assets[index]
+                    }
+                    AssetMatch::Ambiguous(candidates) => {
+                        let candidate_names: Vec<&str> = candidates
+                            .iter()
+                            .map(|&i| asset_names[i])
+                            .collect();
 
-                &assets[selection]
+                        status!("{} Several assets match this platform:", "→".blue());
+                        let selection = self.select(
+                            "Select asset",
+                            &candidate_names,
+                            0,
+                            &format!("ambiguous between {}; pass --asset", candidate_names.join(", ")),
+                        )?;
+
+                        &assets[candidates[selection]]
+                    }
+                    AssetMatch::None => {
+                        status!(
+                            "{} No asset matched {}-{}, available assets:",
+                            "!".yellow(),
+                            utils::get_platform(),
+                            utils::get_arch()
+                        );
+                        let selection = self.select("Select asset", &asset_names, 0, "pass --asset")?;
+
+                        &assets[selection]
+                    }
+                }
             }
         };
 
+        Ok(asset_obj)
+    }
+
+    /// Download, unpack and register one release asset under `packages/<name>/<tag>` in `prefix`,
+    /// with the tag made safe to use as a directory name
+    async fn install_release(
+        &self,
+        package_name: &str,
+        package: &Package,
+        release: &serde_json::Value,
+        asset_obj: &serde_json::Value,
+        prefix: &Prefix,
+        locked_sha256: Option<&str>,
+    ) -> Result<InstalledPackage> {
         let download_url = asset_obj["browser_download_url"]
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Invalid download URL"))?;
@@ -109,69 +265,1269 @@ impl Grip {
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Invalid asset name"))?;
 
-        // Download and install
-        let target_dir = self.registry_manager.data_dir
-            .join("packages")
-            .join(package_name)
-            .join(release["tag_name"].as_str().unwrap_or("unknown"));
+        let tag = release["tag_name"].as_str().unwrap_or("unknown");
+
+        let package_dir = prefix.packages_dir().join(package_name);
+        let target_dir = package_dir.join(installed::tag_dir(tag));
+
+        // Unpack into a staging directory and move it into place at the end, so a failed
+        // install never leaves a half-populated version behind
+        let staging_dir = package_dir.join(format!(".{}.partial", installed::tag_dir(tag)));
+        if staging_dir.exists() {
+            std::fs::remove_dir_all(&staging_dir)?;
+        }
 
-        let downloaded_file = self.registry_manager
-            .download_asset(download_url, filename, &target_dir)
+        let expected = match locked_sha256 {
+            Some(sha256) => {
+                let checksum = Checksum::parse(sha256).ok_or_else(|| {
+                    anyhow::anyhow!("Invalid checksum locked for {}: {}", filename, sha256)
+                })?;
+                status!("{} Using checksum from {}", "→".blue(), lockfile::LOCK_FILE);
+                Some(checksum)
+            }
+            None => self.expected_checksum(package, release, filename).await?,
+        };
+        if expected.is_none() {
+            status!("{} No checksum published for {}, skipping verification", "!".yellow(), filename);
+        }
+
+        let downloaded = self.registry_manager
+            .download_asset(asset_obj, &staging_dir, expected.as_ref())
             .await?;
 
+        if let Err(err) = self.verify_signature(package_name, package, release, filename, &downloaded).await {
+            std::fs::remove_dir_all(&staging_dir)?;
+            return Err(err);
+        }
+
         // Handle archive extraction if needed
-        if filename.ends_with(".zip") || filename.ends_with(".tar.gz") || filename.ends_with(".tgz") {
-            println!("{} Extracting archive...", "→".blue());
-            utils::extract_archive(&downloaded_file, &target_dir);
-            println!("{} Extracted to {:?}", "✓".green(), target_dir);
+        if ArchiveKind::detect(filename).is_some() {
+            status!("{} Extracting archive...", "→".blue());
+            if let Err(err) = archive::extract(&downloaded.path, &staging_dir).await {
+                std::fs::remove_dir_all(&staging_dir)?;
+                return Err(err);
+            }
             
             // Clean up archive after extraction
-            std::fs::remove_file(downloaded_file)?;
+            std::fs::remove_file(&downloaded.path)?;
+        } else {
+            utils::make_executable(&downloaded.path)?;
         }
 
-        // Add to PATH if needed
-        path::add_to_path(&target_dir).await?;
+        // A bare binary is named after the asset, e.g. jq-linux-amd64; expose it as the package instead
+        if ArchiveKind::detect(filename).is_none_or(ArchiveKind::is_single_file) {
+            if let [binary] = utils::find_binaries(&staging_dir)?.as_slice() {
+                let is_exe = binary.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+                let renamed = staging_dir.join(if is_exe {
+                    format!("{}.exe", package_name)
+                } else {
+                    package_name.to_string()
+                });
+                std::fs::rename(binary, renamed)?;
+            }
+        }
+
+        if target_dir.exists() {
+            std::fs::remove_dir_all(&target_dir)?;
+        }
+        std::fs::rename(&staging_dir, &target_dir)?;
+        status!("{} Installed to {:?}", "✓".green(), target_dir);
+
+        // Expose the binaries through the shared bin directory, which is the only thing put on PATH
+        let bin_dir = &prefix.bin_dir;
+        let found = utils::find_binaries(&target_dir)?;
+        if found.is_empty() {
+            status!("{} No executables found in {:?}", "!".yellow(), target_dir);
+        }
+
+        let binaries = path::link_binaries(&found, bin_dir, &prefix.packages_dir(), prefix.global)?;
+        for binary in &binaries {
+            status!("{} Linked {} into {:?}", "✓".green(), binary.cyan(), bin_dir);
+        }
+
+        if prefix.global {
+            path::add_to_path(bin_dir).await?;
+        }
+
+        let installed = InstalledPackage {
+            name: package_name.to_string(),
+            registry: package.registry.clone(),
+            repository: package.info.repository.clone(),
+            tag: tag.to_string(),
+            asset: filename.to_string(),
+            download_url: download_url.to_string(),
+            sha256: downloaded.sha256,
+            installed_at: chrono::Utc::now(),
+            binaries,
+        };
+
+        let mut db = InstalledDb::load(&prefix.root)?;
+        db.record(installed.clone());
+        db.save()?;
+
+        Ok(installed)
+    }
+
+    /// The digest an asset must match. A pin in the registry wins over checksum files published with the release.
+    async fn expected_checksum(
+        &self,
+        package: &Package,
+        release: &serde_json::Value,
+        filename: &str,
+    ) -> Result<Option<Checksum>> {
+        if let Some(pinned) = package.info.checksums.get(filename) {
+            let checksum = Checksum::parse(pinned).ok_or_else(|| {
+                GripError::RegistryError(format!("Invalid checksum pinned for {}: {}", filename, pinned))
+            })?;
+            status!("{} Using {} checksum pinned in registry", "→".blue(), checksum.algorithm);
+            return Ok(Some(checksum));
+        }
+
+        let assets = release["assets"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let names: Vec<&str> = assets
+            .iter()
+            .map(|a| a["name"].as_str().unwrap_or(""))
+            .collect();
+
+        for index in checksum::checksum_assets(filename, &names) {
+            let content = self.registry_manager.fetch_asset_text(&assets[index]).await?;
+            if let Some(checksum) = checksum::find_in_file(&content, filename) {
+                status!("{} Found {} checksum in {}", "→".blue(), checksum.algorithm, names[index].cyan());
+                return Ok(Some(checksum));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Check the release signature of a downloaded asset against the keys declared in the registry.
+    /// A signature over a checksum list that covers the asset counts as well.
+    async fn verify_signature(
+        &self,
+        package_name: &str,
+        package: &Package,
+        release: &serde_json::Value,
+        filename: &str,
+        downloaded: &DownloadedAsset,
+    ) -> Result<()> {
+        let policy = self.config.settings.signature_policy;
+        if policy == SignaturePolicy::Off {
+            return Ok(());
+        }
+
+        let unverified = |reason: String| -> Result<()> {
+            if policy == SignaturePolicy::Require {
+                return Err(GripError::SignatureError(reason).into());
+            }
+            status!("{} {}, skipping signature verification", "!".yellow(), reason);
+            Ok(())
+        };
+
+        let keys = &package.info.signing;
+        if keys.is_empty() {
+            return unverified(format!("No signing keys declared for '{}'", package_name));
+        }
+
+        let assets = release["assets"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let names: Vec<&str> = assets
+            .iter()
+            .map(|a| a["name"].as_str().unwrap_or(""))
+            .collect();
+
+        let mut candidates: Vec<(std::path::PathBuf, signature::SignatureAsset)> =
+            signature::signature_assets(filename, &names, keys)
+                .into_iter()
+                .map(|sig| (downloaded.path.clone(), sig))
+                .collect();
+
+        // Checksum lists that are signed and vouch for exactly the bytes we downloaded
+        let work_dir = tempfile::tempdir()?;
+        for index in checksum::checksum_assets(filename, &names) {
+            let signatures = signature::signature_assets(names[index], &names, keys);
+            if signatures.is_empty() {
+                continue;
+            }
+
+            let content = self.registry_manager.fetch_asset(&assets[index]).await?;
+            let Some(listed) = checksum::find_in_file(&String::from_utf8_lossy(&content), filename) else {
+                continue;
+            };
+            let actual = match listed.algorithm {
+                checksum::Algorithm::Sha256 => downloaded.sha256.clone(),
+                algorithm => checksum::digest_file(&downloaded.path, algorithm)?,
+            };
+            if listed.hex != actual {
+                continue;
+            }
+
+            let list_path = work_dir.path().join(names[index]);
+            std::fs::write(&list_path, &content)?;
+            candidates.extend(signatures.into_iter().map(|sig| (list_path.clone(), sig)));
+        }
+
+        if candidates.is_empty() {
+            return unverified(format!("No signature found for {}", filename));
+        }
+
+        let mut failures = Vec::new();
+        let mut missing_tools = Vec::new();
+        for (target, sig) in candidates {
+            let signature_bytes = self.registry_manager.fetch_asset(&assets[sig.index]).await?;
+            let certificate = match sig.certificate {
+                Some(index) => Some(self.registry_manager.fetch_asset(&assets[index]).await?),
+                None => None,
+            };
+
+            match signature::verify(sig.kind, keys, &target, &signature_bytes, certificate.as_deref()).await {
+                Ok(Verification::Verified) => {
+                    status!("{} Verified {} signature {}", "✓".green(), sig.kind, names[sig.index].cyan());
+                    return Ok(());
+                }
+                Ok(Verification::ToolMissing(tool)) => missing_tools.push(tool),
+                Err(err) => failures.push(format!("{}: {}", names[sig.index], err)),
+            }
+        }
+
+        // A signature that exists but doesn't check out points at tampering, so never let that through
+        if !failures.is_empty() {
+            return Err(GripError::SignatureError(failures.join("; ")).into());
+        }
+
+        unverified(format!("Cannot verify signature for {} ({} not installed)", filename, missing_tools.join(", ")))
+    }
+
+    /// The newest stable release, or the highest version including prereleases with `pre`
+    async fn newest_release(&self, package: &Package, pre: bool) -> Result<Option<serde_json::Value>> {
+        let repo = &package.info.repository;
+        let tag_prefix = package.info.tag_prefix.as_deref();
+
+        // GitHub's latest release may belong to another tool of a monorepo, so those search the list
+        if !pre && tag_prefix.is_none() {
+            return self.registry_manager.get_latest_release(repo).await;
+        }
+        let mut releases = self.registry_manager.get_releases(repo, pre).await?;
+        version::retain_tool(&mut releases, tag_prefix);
+        version::sort_releases(&mut releases);
+        Ok(releases.into_iter().next())
+    }
+
+    /// [`Self::newest_release`], reporting which one was picked
+    async fn latest_release(&self, package: &Package, pre: bool) -> Result<Option<serde_json::Value>> {
+        let latest = self.newest_release(package, pre).await?;
+
+        if let Some(release) = &latest {
+            status!(
+                "{} Using latest release {}",
+                "→".blue(),
+                release["tag_name"].as_str().unwrap_or("unknown").cyan()
+            );
+        }
+        Ok(latest)
+    }
+
+    /// Find the release for a version requirement, including `latest`
+    async fn resolve_requirement(&self, package: &Package, input: &str, pre: bool) -> Result<serde_json::Value> {
+        match Requirement::parse(input) {
+            Requirement::Latest => Ok(self.latest_release(package, pre).await?.ok_or_else(|| {
+                GripError::VersionNotFound(format!("no {}release of '{}'", if pre { "" } else { "stable " }, package.info.name))
+            })?),
+            requirement => self.resolve_version(package, input, &requirement).await,
+        }
+    }
+
+    /// Find the release satisfying a `--version` requirement, preferring an exact tag match
+    async fn resolve_version(&self, package: &Package, input: &str, requirement: &Requirement) -> Result<serde_json::Value> {
+        let repo = &package.info.repository;
+        let tag_prefix = package.info.tag_prefix.as_deref();
+
+        if !matches!(requirement, Requirement::Range(_)) {
+            let exact = self.registry_manager
+                .get_release_by_tag(repo, input)
+                .await?
+                .filter(|r| !r["draft"].as_bool().unwrap_or(false))
+                .filter(|r| version::is_tool_tag(r["tag_name"].as_str().unwrap_or(""), tag_prefix));
+            if let Some(release) = exact {
+                return Ok(release);
+            }
+        }
+        if matches!(requirement, Requirement::Tag(_)) {
+            return Err(GripError::VersionNotFound(input.to_string()).into());
+        }
+
+        // Prereleases are only matched when the requirement names one, following semver rules
+        let mut releases = self.registry_manager.get_releases(repo, true).await?;
+        version::retain_tool(&mut releases, tag_prefix);
+        version::sort_releases(&mut releases);
+        let release = releases
+            .into_iter()
+            .find(|r| requirement.matches(r["tag_name"].as_str().unwrap_or("")))
+            .ok_or_else(|| GripError::VersionNotFound(input.to_string()))?;
+
+        status!(
+            "{} Resolved {} to {}",
+            "→".blue(),
+            input,
+            release["tag_name"].as_str().unwrap_or("unknown").cyan()
+        );
+        Ok(release)
+    }
+
+    /// Work out which installed packages have a newer stable release
+    async fn plan_upgrades(&self, packages: &[String]) -> Result<Vec<Upgrade>> {
+        let db = InstalledDb::load(&self.registry_manager.data_dir)?;
+        let names = if packages.is_empty() { db.names() } else { packages.to_vec() };
+
+        let mut upgrades = Vec::new();
+        for name in names {
+            let current = db
+                .latest(&name)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Package '{}' is not installed", name))?;
+
+            // Resolve through the registries again so pinned checksums and repository moves are honoured
+            let package = self.registry_manager.find_package(&self.config.registries, &name).await?;
+            let repo = &package.info.repository;
+            let Some(latest) = self.newest_release(&package, false).await? else {
+                continue;
+            };
+            if latest["tag_name"].as_str() == Some(current.tag.as_str()) {
+                continue;
+            }
+
+            // Skip packages already on something newer than the latest stable release, such as a prerelease.
+            // Tags that aren't versions are compared by publish date instead.
+            let latest_version = latest["tag_name"].as_str().and_then(version::parse_tag);
+            let up_to_date = match (version::parse_tag(&current.tag), latest_version) {
+                (Some(installed), Some(available)) => installed >= available,
+                _ => {
+                    let installed = self.registry_manager.get_release_by_tag(repo, &current.tag).await?;
+                    let published = |release: &serde_json::Value| release["published_at"].as_str().unwrap_or("").to_string();
+                    installed.is_some_and(|r| published(&r) >= published(&latest))
+                }
+            };
+            if up_to_date {
+                continue;
+            }
+
+            upgrades.push(Upgrade {
+                current,
+                package,
+                release: latest,
+            });
+        }
+
+        Ok(upgrades)
+    }
+
+    fn print_upgrades(upgrades: &[Upgrade]) {
+        for upgrade in upgrades {
+            println!(
+                "  {} {} → {}",
+                upgrade.current.name.cyan(),
+                upgrade.current.tag,
+                upgrade.new_tag().green()
+            );
+        }
+    }
+
+    async fn outdated(&self) -> Result<()> {
+        let upgrades = self.plan_upgrades(&[]).await?;
+
+        if upgrades.is_empty() {
+            println!("{} All packages are up to date", "✓".green());
+        } else {
+            println!("{} Outdated packages:", "→".blue());
+            Self::print_upgrades(&upgrades);
+        }
+
+        Ok(())
+    }
+
+    async fn upgrade(&self, packages: &[String], prune: bool) -> Result<()> {
+        let upgrades = self.plan_upgrades(packages).await?;
+
+        if upgrades.is_empty() {
+            println!("{} All packages are up to date", "✓".green());
+            return Ok(());
+        }
+
+        println!("{} The following packages will be upgraded:", "→".blue());
+        Self::print_upgrades(&upgrades);
+
+        if self.interactive && !Confirm::new().with_prompt("Continue?").default(true).interact()? {
+            return Ok(());
+        }
+
+        // Keep going when one package fails, so a single broken release doesn't hold back the rest
+        let mut failed = Vec::new();
+        for upgrade in &upgrades {
+            if let Err(err) = self.upgrade_package(upgrade, prune).await {
+                eprintln!("{} Failed to upgrade {}: {:#}", "✗".red(), upgrade.current.name.cyan(), err);
+                failed.push(upgrade.current.name.as_str());
+            }
+        }
+
+        let upgraded = upgrades.len() - failed.len();
+        if failed.is_empty() {
+            println!("{} Upgraded {} package(s)", "✓".green(), upgraded);
+            Ok(())
+        } else {
+            println!("{} Upgraded {} of {} package(s)", "!".yellow(), upgraded, upgrades.len());
+            anyhow::bail!("Failed to upgrade {}", failed.join(", "))
+        }
+    }
+
+    async fn upgrade_package(&self, upgrade: &Upgrade, prune: bool) -> Result<()> {
+        let current = &upgrade.current;
+        let new_tag = upgrade.new_tag();
+
+        let asset_names: Vec<&str> = upgrade.release["assets"]
+            .as_array()
+            .map(|assets| assets.iter().map(|a| a["name"].as_str().unwrap_or("")).collect())
+            .unwrap_or_default();
+
+        // Prefer the asset that mirrors the one installed before, so the user's earlier choice sticks
+        let asset = assets::match_previous(&current.asset, &current.tag, new_tag, &asset_names)
+            .map(|index| asset_names[index].to_string());
+        let asset_obj = self.select_asset(&upgrade.release, asset)?;
+
+        self.install_release(&current.name, &upgrade.package, &upgrade.release, asset_obj, &self.global_prefix(), None)
+            .await?;
+
+        // The new version is fully in place and on PATH, only now retire the old ones
+        let package_dir = self.registry_manager.data_dir
+            .join("packages")
+            .join(&current.name);
+        let mut db = InstalledDb::load(&self.registry_manager.data_dir)?;
+
+        let old_tags: Vec<String> = db
+            .versions(&current.name)
+            .iter()
+            .map(|p| p.tag.clone())
+            .filter(|tag| tag != new_tag)
+            .collect();
+
+        let bin_dir = self.config.settings.bin_dir();
+        for tag in old_tags {
+            let old_dir = package_dir.join(installed::tag_dir(&tag));
+            path::unlink_binaries(&bin_dir, &old_dir)?;
+            // Versions installed before the shared bin directory put themselves on PATH directly
+            path::remove_from_path(&old_dir).await?;
+
+            if prune {
+                if old_dir.exists() {
+                    std::fs::remove_dir_all(&old_dir)?;
+                }
+                db.remove(&current.name, Some(&tag));
+            }
+        }
+        db.save()?;
+
+        println!(
+            "{} Upgraded {} {} → {}",
+            "✓".green(),
+            current.name.cyan(),
+            current.tag,
+            new_tag.green()
+        );
+        Ok(())
+    }
+
+    async fn uninstall(&self, package_name: &str, version: Option<String>) -> Result<()> {
+        // Both end up joined onto the packages directory, so `..` must not get through
+        if !utils::is_plain_component(package_name) {
+            anyhow::bail!("Invalid package name '{}'", package_name);
+        }
+        if let Some(ref v) = version {
+            // Tags may contain slashes, but never anything that walks out of the directory
+            if !Path::new(v).components().all(|c| matches!(c, Component::Normal(_))) {
+                anyhow::bail!("Invalid version '{}'", v);
+            }
+        }
+
+        let package_dir = self.registry_manager.data_dir
+            .join("packages")
+            .join(package_name);
+
+        let mut db = InstalledDb::load(&self.registry_manager.data_dir)?;
+
+        if !package_dir.exists() {
+            if db.versions(package_name).is_empty() {
+                anyhow::bail!("Package '{}' is not installed", package_name);
+            }
+
+            // Files are already gone, just forget about them
+            db.remove(package_name, version.as_deref());
+            db.save()?;
+            println!("{} Uninstalled {}", "✓".green(), package_name.cyan());
+            return Ok(());
+        }
+
+        let version_dirs = match version {
+            Some(ref v) => {
+                let dir = package_dir.join(installed::tag_dir(v));
+                if !dir.is_dir() {
+                    return Err(GripError::VersionNotFound(format!("{} {}", package_name, v)).into());
+                }
+                vec![dir]
+            }
+            None => std::fs::read_dir(&package_dir)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect(),
+        };
+
+        if self.interactive {
+            let what = match version {
+                Some(ref v) => format!("{} {}", package_name, v),
+                None => package_name.to_string(),
+            };
+            let confirmed = Confirm::new()
+                .with_prompt(format!("Uninstall {}?", what))
+                .default(true)
+                .interact()?;
+            if !confirmed {
+                return Ok(());
+            }
+        }
+
+        let bin_dir = self.config.settings.bin_dir();
+        for dir in &version_dirs {
+            path::unlink_binaries(&bin_dir, dir)?;
+            path::remove_from_path(dir).await?;
+            std::fs::remove_dir_all(dir)?;
+            println!(
+                "{} Removed {} {}",
+                "✓".green(),
+                package_name.cyan(),
+                dir.file_name().unwrap_or_default().to_string_lossy()
+            );
+        }
+
+        // Drop the package directory once no versions are left in it
+        if std::fs::read_dir(&package_dir)?.next().is_none() {
+            std::fs::remove_dir(&package_dir)?;
+        }
+
+        db.remove(package_name, version.as_deref());
+        db.save()?;
+
+        println!("{} Uninstalled {}", "✓".green(), package_name.cyan());
+        Ok(())
+    }
+
+    /// The manifest of the project the current directory belongs to
+    fn project(&self) -> Result<Manifest> {
+        Manifest::find(&std::env::current_dir()?)?.ok_or_else(|| {
+            anyhow::anyhow!("No {} found in this directory or its parents (run 'grip init')", manifest::MANIFEST_FILE)
+        })
+    }
+
+    /// Install every dependency declared in `grip.json` into the project's `.grip` directory.
+    ///
+    /// Dependencies whose lock entry still matches the manifest get exactly the locked artifact, the
+    /// rest are resolved again. With `locked` the lock must match the manifest completely. Names in
+    /// `update` are always resolved again; an empty list means all of them.
+    async fn install_project(&self, locked: bool, update: Option<&[String]>) -> Result<()> {
+        let manifest = self.project()?;
+        let prefix = manifest.prefix();
+        let lock = Lockfile::load(manifest.dir())?;
+
+        if locked {
+            let lock = lock.as_ref().ok_or_else(|| {
+                anyhow::anyhow!("No {} found (run 'grip install' to create it)", lockfile::LOCK_FILE)
+            })?;
+            let problems = lock.mismatches(&manifest);
+            if !problems.is_empty() {
+                anyhow::bail!(
+                    "{} is out of date with {} (run 'grip update'):\n  {}",
+                    lockfile::LOCK_FILE,
+                    manifest::MANIFEST_FILE,
+                    problems.join("\n  ")
+                );
+            }
+        }
+
+        if let Some(names) = update {
+            if let Some(name) = names.iter().find(|name| !manifest.dependencies.contains_key(*name)) {
+                anyhow::bail!("'{}' is not a dependency in {}", name, manifest.path().display());
+            }
+        }
+
+        std::fs::create_dir_all(&prefix.root)?;
+
+        // Tools dropped from the manifest go away
+        let db = InstalledDb::load(&prefix.root)?;
+        for name in db.names() {
+            if !manifest.dependencies.contains_key(&name) {
+                Self::remove_project_tool(&prefix, &name, None)?;
+                println!("{} Removed {} (no longer in {})", "✓".green(), name.cyan(), manifest::MANIFEST_FILE);
+            }
+        }
+
+        let mut new_lock = Lockfile::new(manifest.dir());
+        let registries = manifest.registries(&self.config.registries);
+        for (name, dependency) in &manifest.dependencies {
+            let refresh = update.is_some_and(|names| names.is_empty() || names.contains(name));
+            let pinned = lock
+                .as_ref()
+                .and_then(|lock| lock.get(name))
+                .filter(|entry| {
+                    !refresh
+                        && entry.requirement == dependency.version()
+                        && dependency.asset().is_none_or(|asset| asset == entry.asset)
+                        && dependency.registry().is_none_or(|registry| registry == entry.registry)
+                });
+
+            let entry = self.install_dependency(&prefix, &registries, name, dependency, pinned).await?;
+            new_lock.packages.push(entry);
+        }
+
+        if !locked {
+            new_lock.save()?;
+        }
+
+        if manifest.dependencies.is_empty() {
+            println!("{} No dependencies declared in {}", "!".yellow(), manifest.path().display());
+        } else {
+            println!(
+                "{} {} tools ready in {}",
+                "✓".green(),
+                manifest.dependencies.len(),
+                prefix.bin_dir.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Bring one project dependency to the locked artifact, or to the release its requirement resolves to
+    async fn install_dependency(
+        &self,
+        prefix: &Prefix,
+        registries: &[config::Registry],
+        name: &str,
+        dependency: &Dependency,
+        locked: Option<&LockedPackage>,
+    ) -> Result<LockedPackage> {
+        let registry = locked.map(|l| l.registry.as_str()).or(dependency.registry());
+        let registries: Vec<config::Registry> = match registry {
+            Some(registry) => {
+                let selected: Vec<_> = registries.iter().filter(|r| r.name == registry).cloned().collect();
+                if selected.is_empty() {
+                    return Err(GripError::RegistryNotFound(registry.to_string()).into());
+                }
+                selected
+            }
+            None => registries.to_vec(),
+        };
+
+        let package = self.registry_manager.find_package(&registries, name).await?;
+        let repo = &package.info.repository;
+
+        let (release, asset) = match locked {
+            Some(locked) => {
+                // Never fetch a locked artifact from somewhere else than it was locked from
+                if package.info.repository != locked.repository {
+                    anyhow::bail!(
+                        "{} now points {} at {} but {} has {} (run 'grip update {}')",
+                        locked.registry,
+                        name,
+                        package.info.repository,
+                        lockfile::LOCK_FILE,
+                        locked.repository,
+                        name
+                    );
+                }
+                let release = self.registry_manager
+                    .get_release_by_tag(repo, &locked.tag)
+                    .await?
+                    .ok_or_else(|| GripError::VersionNotFound(format!("{} {} (locked)", name, locked.tag)))?;
+                (release, Some(locked.asset.as_str()))
+            }
+            None => {
+                println!("{} Resolving {} {}", "→".blue(), name.cyan(), dependency.version());
+                let release = self.resolve_requirement(&package, dependency.version(), false).await?;
+                (release, dependency.asset())
+            }
+        };
+        let tag = release["tag_name"].as_str().unwrap_or("unknown");
+
+        let db = InstalledDb::load(&prefix.root)?;
+        let current = db.versions(name).into_iter().find(|p| {
+            p.tag == tag
+                && asset.is_none_or(|a| p.asset == a)
+                && locked.is_none_or(|l| p.sha256 == l.sha256)
+        });
+        if let Some(current) = current.filter(|_| prefix.packages_dir().join(name).join(installed::tag_dir(tag)).is_dir()) {
+            println!("{} {} {} is up to date", "✓".green(), name.cyan(), tag);
+            return Ok(LockedPackage::new(dependency.version(), current));
+        }
+
+        let asset_obj = self.select_asset(&release, asset.map(str::to_string))?;
+        if let Some(locked) = locked {
+            let url = asset_obj["browser_download_url"].as_str().unwrap_or("");
+            if url != locked.download_url {
+                anyhow::bail!(
+                    "{} {} is now served from {} but {} has {}",
+                    name,
+                    locked.asset,
+                    url,
+                    lockfile::LOCK_FILE,
+                    locked.download_url
+                );
+            }
+        }
+        let locked_sha256 = locked.map(|l| l.sha256.as_str());
+        let installed = self
+            .install_release(name, &package, &release, asset_obj, prefix, locked_sha256)
+            .await?;
+
+        // A project holds a single version of each tool
+        Self::remove_project_tool(prefix, name, Some(&installed.tag))?;
+        Ok(LockedPackage::new(dependency.version(), &installed))
+    }
+
+    /// Delete a tool from a project's `.grip` directory, except for the version in `keep`
+    fn remove_project_tool(prefix: &Prefix, name: &str, keep: Option<&str>) -> Result<()> {
+        manifest::check_dependency_name(name)?;
+        let mut db = InstalledDb::load(&prefix.root)?;
+        let package_dir = prefix.packages_dir().join(name);
+
+        let stale: Vec<String> = db
+            .versions(name)
+            .iter()
+            .map(|p| p.tag.clone())
+            .filter(|tag| Some(tag.as_str()) != keep)
+            .collect();
+
+        for tag in stale {
+            let dir = package_dir.join(installed::tag_dir(&tag));
+            path::unlink_binaries(&prefix.bin_dir, &dir)?;
+            if dir.exists() {
+                std::fs::remove_dir_all(&dir)?;
+            }
+            db.remove(name, Some(&tag));
+        }
+
+        if package_dir.is_dir() && std::fs::read_dir(&package_dir)?.next().is_none() {
+            std::fs::remove_dir(&package_dir)?;
+        }
+
+        db.save()
+    }
+
+    /// Declare a dependency in `grip.json`, defaulting to releases compatible with the latest one
+    async fn add(&self, package_name: &str, version: Option<String>, asset: Option<String>) -> Result<()> {
+        manifest::check_dependency_name(package_name)?;
+        let mut manifest = self.project()?;
+        let registries = manifest.registries(&self.config.registries);
+        let package = self.registry_manager.find_package(&registries, package_name).await?;
+
+        let version = match version {
+            Some(version) => version,
+            None => {
+                let latest = self
+                    .resolve_requirement(&package, "latest", false)
+                    .await?;
+                let tag = latest["tag_name"].as_str().unwrap_or("latest");
+                match version::parse_tag(tag) {
+                    Some(version) => format!("^{}", version),
+                    None => tag.to_string(),
+                }
+            }
+        };
+
+        println!("{} Added {} {} to {}", "✓".green(), package_name.cyan(), version, manifest::MANIFEST_FILE);
+        manifest
+            .dependencies
+            .insert(package_name.to_string(), Dependency::new(version, asset));
+        manifest.save()?;
+
+        println!("{} Run 'grip install' to install it", "→".blue());
+        Ok(())
+    }
+
+    /// Drop a dependency from `grip.json` and `grip.lock` along with its files in `.grip`
+    async fn remove(&self, package_name: &str) -> Result<()> {
+        let mut manifest = self.project()?;
+        if manifest.dependencies.remove(package_name).is_none() {
+            anyhow::bail!("'{}' is not a dependency in {}", package_name, manifest.path().display());
+        }
+        manifest.save()?;
+
+        if let Some(mut lock) = Lockfile::load(manifest.dir())? {
+            lock.packages.retain(|p| p.name != package_name);
+            lock.save()?;
+        }
+
+        let prefix = manifest.prefix();
+        if prefix.root.is_dir() {
+            Self::remove_project_tool(&prefix, package_name, None)?;
+        }
+
+        println!("{} Removed {} from {}", "✓".green(), package_name.cyan(), manifest::MANIFEST_FILE);
+        Ok(())
+    }
+
+    /// Run a package's binary from grip's exec cache, installing the release there first if needed.
+    /// Nothing is linked onto PATH. Returns the exit code of the binary.
+    async fn exec(&self, spec: &str, bin: Option<String>, args: &[String]) -> Result<i32> {
+        utils::send_status_to_stderr();
+        let (name, version) = match spec.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (spec, None),
+        };
+
+        let prefix = Prefix {
+            root: self.registry_manager.data_dir.join("exec"),
+            bin_dir: self.registry_manager.data_dir.join("exec").join("bin"),
+            global: false,
+        };
+        std::fs::create_dir_all(&prefix.root)?;
+
+        // A version that is already cached runs without asking GitHub. Without a version, the release
+        // last resolved as the latest is trusted for the cache TTL; `@latest` always checks.
+        let requirement = version.map(Requirement::parse);
+        let ttl = chrono::Duration::seconds(self.config.cache_ttl as i64);
+        let mut db = InstalledDb::load(&prefix.root)?;
+        let cached = match &requirement {
+            Some(Requirement::Latest) => None,
+            requirement => db.cached(name, requirement.as_ref(), ttl),
+        }
+        .filter(|p| prefix.packages_dir().join(name).join(installed::tag_dir(&p.tag)).is_dir());
+
+        let tag = match cached {
+            Some(installed) => installed.tag.clone(),
+            None => {
+                let package = self.registry_manager.find_package(&self.config.registries, name).await?;
+                let release = self
+                    .resolve_requirement(&package, version.unwrap_or("latest"), false)
+                    .await?;
+                let tag = release["tag_name"].as_str().unwrap_or("unknown").to_string();
+
+                let existing = db.versions(name).into_iter().find(|p| p.tag == tag).cloned();
+                match existing.filter(|_| prefix.packages_dir().join(name).join(installed::tag_dir(&tag)).is_dir()) {
+                    // Still the latest, so it counts as fresh again
+                    Some(mut installed) => {
+                        installed.installed_at = chrono::Utc::now();
+                        db.record(installed);
+                        db.save()?;
+                    }
+                    None => {
+                        let asset_obj = self.select_asset(&release, None)?;
+                        self.install_release(name, &package, &release, asset_obj, &prefix, None)
+                            .await?;
+                    }
+                }
+                tag
+            }
+        };
+
+        let binaries = utils::find_binaries(&prefix.packages_dir().join(name).join(installed::tag_dir(&tag)))?;
+        let stem = |path: &std::path::PathBuf| {
+            path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+        };
+        let wanted = bin.as_deref().unwrap_or(name);
+        let binary = match binaries.iter().find(|b| stem(b) == wanted) {
+            Some(binary) => binary,
+            None if bin.is_none() && binaries.len() == 1 => &binaries[0],
+            None => {
+                let available: Vec<String> = binaries.iter().map(stem).collect();
+                anyhow::bail!(
+                    "No binary named '{}' in {} {} (available: {}; pick one with --bin)",
+                    wanted,
+                    name,
+                    tag,
+                    available.join(", ")
+                );
+            }
+        };
+
+        utils::run_binary(binary, args)
+    }
+
+    /// Make `name@version` the version exposed in the bin directory wherever no pin applies. Without a
+    /// version, show what the nearest `.grip-version` or `grip.json` pins for this directory; shims
+    /// switch to those versions on their own.
+    async fn use_version(&self, spec: Option<&str>) -> Result<()> {
+        if let Some((name, version)) = spec.and_then(|spec| spec.split_once('@')) {
+            return self.activate(name, version);
+        }
+
+        let (source, mut overrides) = manifest::version_overrides(&std::env::current_dir()?)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No version given and no {} or {} found (use name@version)",
+                manifest::VERSION_FILE,
+                manifest::MANIFEST_FILE
+            )
+        })?;
+        if let Some(name) = spec {
+            if !overrides.contains_key(name) {
+                anyhow::bail!("{} does not pin a version of '{}' (use {}@version)", source.display(), name, name);
+            }
+            overrides.retain(|pinned, _| pinned == name);
+        }
+
+        println!("{} Versions pinned by {}:", "→".blue(), source.display());
+        let db = InstalledDb::load(&self.registry_manager.data_dir)?;
+        for (name, version) in &overrides {
+            match db.newest_matching(name, &Requirement::parse(version)) {
+                Some(installed) => println!("  {} {} → {}", name.cyan(), version, installed.tag.green()),
+                None => println!(
+                    "  {} {} {} (run 'grip install {} --version {}')",
+                    name.cyan(),
+                    version,
+                    "not installed".yellow(),
+                    name,
+                    version
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Make the newest installed version matching `version` the one exposed in the bin directory
+    fn activate(&self, name: &str, version: &str) -> Result<()> {
+        if !utils::is_plain_component(name) {
+            anyhow::bail!("Invalid package name '{}'", name);
+        }
+        let db = InstalledDb::load(&self.registry_manager.data_dir)?;
+        let package_dir = self.registry_manager.data_dir.join("packages").join(name);
+        if db.versions(name).is_empty() {
+            anyhow::bail!("Package '{}' is not installed", name);
+        }
+
+        let chosen = db
+            .newest_matching(name, &Requirement::parse(version))
+            .filter(|p| package_dir.join(installed::tag_dir(&p.tag)).is_dir())
+            .ok_or_else(|| {
+                GripError::VersionNotFound(format!(
+                    "{} {} is not installed (run 'grip install {} --version {}')",
+                    name, version, name, version
+                ))
+            })?;
+
+        let bin_dir = self.config.settings.bin_dir();
+        for installed in db.versions(name) {
+            path::unlink_binaries(&bin_dir, &package_dir.join(installed::tag_dir(&installed.tag)))?;
+        }
+
+        let target_dir = package_dir.join(installed::tag_dir(&chosen.tag));
+        let found = utils::find_binaries(&target_dir)?;
+        let packages_dir = self.registry_manager.data_dir.join("packages");
+        let linked = path::link_binaries(&found, &bin_dir, &packages_dir, true)?;
+
+        println!(
+            "{} Using {} {} ({})",
+            "✓".green(),
+            name.cyan(),
+            chosen.tag.green(),
+            linked.join(", ")
+        );
+        Ok(())
+    }
+
+    /// Run a binary exposed through a shim in the bin directory. `target` is the binary of the version
+    /// made active with `grip use`; a version pinned for the current directory takes its place.
+    fn shim(&self, target: &Path, args: &[String]) -> Result<i32> {
+        utils::send_status_to_stderr();
+        let packages_dir = self.registry_manager.data_dir.join("packages");
+        let Some((name, inner)) = path::package_binary(&packages_dir, target) else {
+            return utils::run_binary(target, args);
+        };
+
+        let pinned = manifest::version_overrides(&std::env::current_dir()?)?
+            .and_then(|(source, overrides)| Some((source, overrides.get(&name)?.clone())));
+        let Some((source, version)) = pinned else {
+            return utils::run_binary(target, args);
+        };
+
+        let db = InstalledDb::load(&self.registry_manager.data_dir)?;
+        let installed = db.newest_matching(&name, &Requirement::parse(&version)).ok_or_else(|| {
+            GripError::VersionNotFound(format!(
+                "{} {} is pinned by {} but not installed (run 'grip install {} --version {}')",
+                name,
+                version,
+                source.display(),
+                name,
+                version
+            ))
+        })?;
+
+        // Archives often put the version in a folder name, so fall back to a binary of the same name
+        let version_dir = packages_dir.join(&name).join(installed::tag_dir(&installed.tag));
+        let mut binary = version_dir.join(&inner);
+        if !binary.is_file() {
+            let file_name = target.file_name().unwrap_or_default();
+            binary = utils::find_binaries(&version_dir)?
+                .into_iter()
+                .find(|b| b.file_name() == Some(file_name))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} {} has no binary named {}",
+                        name,
+                        installed.tag,
+                        file_name.to_string_lossy()
+                    )
+                })?;
+        }
+
+        utils::run_binary(&binary, args)
+    }
+
+    /// List the installed versions of a package, marking the one exposed in the bin directory
+    async fn versions(&self, name: &str) -> Result<()> {
+        let db = InstalledDb::load(&self.registry_manager.data_dir)?;
+        let mut versions = db.versions(name);
+        if versions.is_empty() {
+            anyhow::bail!("Package '{}' is not installed", name);
+        }
+        versions.sort_by_key(|p| std::cmp::Reverse((version::parse_tag(&p.tag), p.installed_at)));
+
+        let bin_dir = self.config.settings.bin_dir();
+        let package_dir = self.registry_manager.data_dir.join("packages").join(name);
+        for installed in versions {
+            let active = !path::linked_binaries(&bin_dir, &package_dir.join(installed::tag_dir(&installed.tag)))?.is_empty();
+            let marker = if active { "*".green() } else { " ".normal() };
+            let tag = if active { installed.tag.green() } else { installed.tag.normal() };
+            println!(
+                "{} {:<20} {}",
+                marker,
+                tag,
+                installed.installed_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Show a package's registry metadata, its latest (or the requested) release and the assets
+    /// the matcher would pick for this platform
+    async fn info(&self, name: &str, version: Option<String>) -> Result<()> {
+        let package = self.registry_manager.find_package(&self.config.registries, name).await?;
+        let info = &package.info;
+        let repo = &info.repository;
+
+        let latest = self.newest_release(&package, false).await?;
+        let release = match version {
+            Some(ref v) => Some(self.resolve_requirement(&package, v, false).await?),
+            None => latest.clone(),
+        };
+
+        println!();
+        println!("{} {}", info.name.cyan().bold(), format!("(registry: {})", package.registry).dimmed());
+        if let Some(description) = &info.description {
+            println!("  {}", description);
+        }
+        println!();
+
+        let field = |label: &str, value: &str| println!("  {:<11} {}", label, value);
+        field("Repository", &format!("https://github.com/{}", repo));
+        if let Some(homepage) = &info.homepage {
+            field("Homepage", homepage);
+        }
+        if let Some(license) = &info.license {
+            field("License", license);
+        }
+        if !info.tags.is_empty() {
+            field("Tags", &info.tags.join(", "));
+        }
+
+        field("Latest", &latest.as_ref().map(registry::describe_release).unwrap_or_else(|| "no stable release".to_string()));
+        if version.is_some() {
+            if let Some(release) = &release {
+                field("Release", &registry::describe_release(release));
+            }
+        }
+
+        let db = InstalledDb::load(&self.registry_manager.data_dir)?;
+        let mut installed = db.versions(name);
+        installed.sort_by_key(|p| (version::parse_tag(&p.tag), p.installed_at));
+        let bin_dir = self.config.settings.bin_dir();
+        let package_dir = self.registry_manager.data_dir.join("packages").join(name);
+        let mut versions = Vec::new();
+        for p in installed {
+            if path::linked_binaries(&bin_dir, &package_dir.join(installed::tag_dir(&p.tag)))?.is_empty() {
+                versions.push(p.tag.clone());
+            } else {
+                versions.push(format!("{} (active)", p.tag));
+            }
+        }
+        field("Installed", &if versions.is_empty() { "no".to_string() } else { versions.join(", ") });
+
+        let Some(release) = release else {
+            return Ok(());
+        };
+        let assets = release["assets"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let names: Vec<&str> = assets.iter().map(|a| a["name"].as_str().unwrap_or("")).collect();
+
+        let platform = format!("{}-{}", utils::get_platform(), utils::get_arch());
+        let (picked, summary) = match assets::match_asset(&names) {
+            AssetMatch::Best(index) => (vec![index], format!("{} would be installed", "✓".green())),
+            AssetMatch::Ambiguous(candidates) => (candidates, format!("{} candidates, pass --asset to choose", "?".yellow())),
+            AssetMatch::None => (Vec::new(), "nothing matched this platform".to_string()),
+        };
+
+        println!();
+        println!("Assets for {} ({}):", platform, summary);
+
+        let marker = if picked.len() == 1 { "✓".green() } else { "?".yellow() };
+        let name_width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+        for (index, asset) in assets.iter().enumerate() {
+            let size = indicatif::HumanBytes(asset["size"].as_u64().unwrap_or(0));
+            let asset_name = format!("{:<name_width$}", names[index]);
+            if picked.contains(&index) {
+                println!("  {} {}  {}", marker, asset_name.cyan(), size);
+            } else {
+                println!("    {}  {}", asset_name, size);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Search package definitions in all configured registries
+    async fn search(&self, query: Option<&str>, tags: &[String], json: bool) -> Result<()> {
+        let packages = self.registry_manager.list_packages(&self.config.registries).await?;
+
+        let hits = search::rank(&packages, query.unwrap_or(""), tags);
+
+        if json {
+            let results: Vec<serde_json::Value> = hits
+                .iter()
+                .map(|(score, package)| {
+                    serde_json::json!({
+                        "name": package.info.name,
+                        "registry": package.registry,
+                        "repository": package.info.repository,
+                        "description": package.info.description,
+                        "homepage": package.info.homepage,
+                        "license": package.info.license,
+                        "tags": package.info.tags,
+                        "score": score,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&results)?);
+            return Ok(());
+        }
+
+        if hits.is_empty() {
+            println!("{} No packages found", "→".blue());
+            return Ok(());
+        }
+
+        let name_width = hits.iter().map(|(_, p)| p.info.name.len()).max().unwrap_or(0).max(4);
+        let registry_width = hits.iter().map(|(_, p)| p.registry.len()).max().unwrap_or(0).max(8);
+
+        println!("{:<name_width$}  {:<registry_width$}  DESCRIPTION", "NAME", "REGISTRY");
+        for (_, package) in hits {
+            let tags = if package.info.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", package.info.tags.join(", "))
+            };
+            println!(
+                "{}  {:<registry_width$}  {}{}",
+                format!("{:<name_width$}", package.info.name).cyan(),
+                package.registry,
+                package.info.description.as_deref().unwrap_or(""),
+                tags.dimmed()
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn list(&self, json: bool) -> Result<()> {
+        let db = InstalledDb::load(&self.registry_manager.data_dir)?;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&db.packages)?);
+            return Ok(());
+        }
+
+        if db.packages.is_empty() {
+            println!("{} No packages installed", "→".blue());
+            return Ok(());
+        }
+
+        let mut packages: Vec<&InstalledPackage> = db.packages.iter().collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name).then(a.installed_at.cmp(&b.installed_at)));
+
+        let name_width = packages.iter().map(|p| p.name.len()).max().unwrap_or(0).max(4);
+        let tag_width = packages.iter().map(|p| p.tag.len()).max().unwrap_or(0).max(7);
+        let registry_width = packages.iter().map(|p| p.registry.len()).max().unwrap_or(0).max(8);
+
+        println!(
+            "{:<name_width$}  {:<tag_width$}  {:<registry_width$}  {:<16}  BINARIES",
+            "NAME", "VERSION", "REGISTRY", "INSTALLED"
+        );
+        for package in packages {
+            println!(
+                "{}  {:<tag_width$}  {:<registry_width$}  {:<16}  {}",
+                format!("{:<name_width$}", package.name).cyan(),
+                package.tag,
+                package.registry,
+                package.installed_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                package.binaries.join(", ")
+            );
+        }
 
-        println!("{} Installation complete!", "✓".green());
         Ok(())
     }
 
     async fn handle_registry_command(&mut self, cmd: RegistryCommands) -> Result<()> {
         match cmd {
             RegistryCommands::Add { name, url, priority } => {
-                if self.config.registries.iter().any(|r| r.name == name) {
+                if self.config.has_registry(&name) {
                     anyhow::bail!("Registry '{}' already exists", name);
                 }
 
+                // Local registries are stored as absolute paths so they work from any directory
+                let source = match url.parse::<RegistrySource>()? {
+                    RegistrySource::Local(path) => {
+                        let path = std::path::absolute(&path)?;
+                        if !path.join("packages").is_dir() {
+                            anyhow::bail!("{} has no packages directory", path.display());
+                        }
+                        RegistrySource::Local(path)
+                    }
+                    source => source,
+                };
+
                 self.config.registries.push(config::Registry {
                     name: name.clone(),
-                    url: url.clone(),
+                    source: source.clone(),
                     priority: priority.unwrap_or(0),
                 });
 
                 self.config.save()?;
-                println!("{} Added registry {} ({})", "✓".green(), name.cyan(), url);
+                println!("{} Added registry {} ({})", "✓".green(), name.cyan(), source);
             }
             RegistryCommands::Remove { name } => {
                 if name == "default" {
                     anyhow::bail!("Cannot remove default registry");
                 }
 
-                let original_len = self.config.registries.len();
-                self.config.registries.retain(|r| r.name != name);
-
-                if self.config.registries.len() == original_len {
+                if !self.config.has_registry(&name) {
                     anyhow::bail!("Registry '{}' not found", name);
                 }
+                // An entry whose URL doesn't parse has nothing synced, only the entry goes
+                self.config.invalid_registries.retain(|entry| entry["name"] != name.as_str());
+                let removed: Vec<config::Registry> = self.config.registries
+                    .iter()
+                    .filter(|r| r.name == name)
+                    .cloned()
+                    .collect();
+                self.config.registries.retain(|r| r.name != name);
 
                 self.config.save()?;
 
                 // Remove cached registry
-                let registry_path = self.registry_manager.data_dir
-                    .join("registries")
-                    .join(&name);
-                if registry_path.exists() {
-                    std::fs::remove_dir_all(registry_path)?;
+                for registry in &removed {
+                    self.registry_manager.remove_checkout(registry)?;
                 }
 
                 println!("{} Removed registry {}", "✓".green(), name.cyan());
@@ -179,43 +1535,130 @@ impl Grip {
             RegistryCommands::List => {
                 println!("{} Configured registries:", "→".blue());
                 for registry in &self.config.registries {
-                    println!("  {} {} (priority: {}, url: {})",
+                    let synced = match self.registry_manager.last_synced(registry) {
+                        Some(time) => time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+                        None => "never".to_string(),
+                    };
+                    println!("  {} {} (priority: {}, url: {}, synced: {})",
                         "→".blue(),
                         registry.name.cyan(),
                         registry.priority,
-                        registry.url
+                        registry.source,
+                        synced
+                    );
+                }
+                for entry in &self.config.invalid_registries {
+                    println!("  {} {} (invalid url: {})",
+                        "✗".red(),
+                        entry["name"].as_str().unwrap_or("<unnamed>").cyan(),
+                        entry["url"]
                     );
                 }
             }
+            RegistryCommands::Update { name } => {
+                let registries: Vec<&config::Registry> = self.config.registries
+                    .iter()
+                    .filter(|r| name.as_ref().is_none_or(|n| &r.name == n))
+                    .collect();
+                if let Some(name) = name.filter(|_| registries.is_empty()) {
+                    return Err(GripError::RegistryNotFound(name).into());
+                }
+
+                let registries: Vec<config::Registry> = registries.into_iter().cloned().collect();
+                let failures = self.registry_manager.sync_registries(&registries).await;
+                for (name, err) in &failures {
+                    eprintln!("{} {}: {:#}", "✗".red(), name.cyan(), err);
+                }
+                if !failures.is_empty() {
+                    anyhow::bail!("{} of {} registries failed to sync", failures.len(), registries.len());
+                }
+            }
         }
         Ok(())
     }
 
     async fn init(&self) -> Result<()> {
-        let config = serde_json::json!({
-            "name": "grip-project",
-            "version": "0.1.0",
-            "dependencies": {}
-        });
+        let dir = std::env::current_dir()?;
+        let manifest = Manifest::new(&dir);
+        if manifest.path().exists() {
+            anyhow::bail!("{} already exists", manifest::MANIFEST_FILE);
+        }
 
-        std::fs::write(
-            "grip.json",
-            serde_json::to_string_pretty(&config)?,
-        )?;
+        manifest.save()?;
 
-        println!("{} Created grip.json", "✓".green());
+        println!("{} Created {}", "✓".green(), manifest::MANIFEST_FILE);
         Ok(())
     }
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let cli = Cli::parse();
-    let mut grip = Grip::new().await?;
+
+    if let Err(err) = run(cli).await {
+        eprintln!("{} {:#}", "✗".red(), err);
+        std::process::exit(error::exit_code(&err));
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let interactive = !cli.yes && std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    let sync = if cli.offline {
+        Some(SyncPolicy::Offline)
+    } else if cli.refresh {
+        Some(SyncPolicy::Refresh)
+    } else {
+        None
+    };
+    let mut grip = Grip::new(interactive, sync).await?;
 
     match cli.command {
-        Commands::Install { package, version, asset } => {
-            grip.install(&package, version, asset).await?;
+        Commands::Install { package: Some(package), version, asset, pre, .. } => {
+            grip.install(&package, version, asset, pre).await?;
+        }
+        Commands::Install { package: None, locked, .. } => {
+            grip.install_project(locked, None).await?;
+        }
+        Commands::Update { packages } => {
+            grip.install_project(false, Some(&packages)).await?;
+        }
+        Commands::Add { package, version, asset } => {
+            grip.add(&package, version, asset).await?;
+        }
+        Commands::Remove { package } => {
+            grip.remove(&package).await?;
+        }
+        Commands::Uninstall { package, version } => {
+            grip.uninstall(&package, version).await?;
+        }
+        Commands::Upgrade { packages, prune } => {
+            grip.upgrade(&packages, prune).await?;
+        }
+        Commands::Outdated => {
+            grip.outdated().await?;
+        }
+        Commands::Exec { package, bin, args } => {
+            let code = grip.exec(&package, bin, &args).await?;
+            std::process::exit(code);
+        }
+        Commands::Use { package } => {
+            grip.use_version(package.as_deref()).await?;
+        }
+        Commands::Shim { target, args } => {
+            let code = grip.shim(&target, &args)?;
+            std::process::exit(code);
+        }
+        Commands::Versions { package } => {
+            grip.versions(&package).await?;
+        }
+        Commands::Info { package, version } => {
+            grip.info(&package, version).await?;
+        }
+        Commands::Search { query, tags, json } => {
+            grip.search(query.as_deref(), &tags, json).await?;
+        }
+        Commands::List { json } => {
+            grip.list(json).await?;
         }
         Commands::Registry { cmd } => {
             grip.handle_registry_command(cmd).await?;
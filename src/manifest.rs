@@ -0,0 +1,283 @@
+use crate::config::Registry;
+use crate::error::Result;
+use crate::installed::Prefix;
+use crate::source::RegistrySource;
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "grip.json";
+pub const VERSION_FILE: &str = ".grip-version";
+
+/// A tool a project depends on, either just a version requirement or a table with overrides
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Dependency {
+    /// `"ripgrep": "^14"`
+    Version(String),
+    /// `"protoc": { "version": "~25", "asset": "protoc-25.1-linux-x86_64.zip" }`
+    Detailed(DependencyDetails),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyDetails {
+    #[serde(default = "latest")]
+    pub version: String,
+    /// Asset to install instead of the one picked for the host platform
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+    /// Only look the package up in this registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+}
+
+fn latest() -> String {
+    "latest".to_string()
+}
+
+impl Dependency {
+    pub fn new(version: String, asset: Option<String>) -> Self {
+        match asset {
+            None => Dependency::Version(version),
+            asset => Dependency::Detailed(DependencyDetails {
+                version,
+                asset,
+                registry: None,
+            }),
+        }
+    }
+
+    pub fn version(&self) -> &str {
+        match self {
+            Dependency::Version(version) => version,
+            Dependency::Detailed(details) => &details.version,
+        }
+    }
+
+    pub fn asset(&self) -> Option<&str> {
+        match self {
+            Dependency::Version(_) => None,
+            Dependency::Detailed(details) => details.asset.as_deref(),
+        }
+    }
+
+    pub fn registry(&self) -> Option<&str> {
+        match self {
+            Dependency::Version(_) => None,
+            Dependency::Detailed(details) => details.registry.as_deref(),
+        }
+    }
+}
+
+/// Dependency names become directories under `.grip/packages`, so they must be a single plain path component
+pub fn check_dependency_name(name: &str) -> Result<()> {
+    if !utils::is_plain_component(name) {
+        anyhow::bail!("Invalid dependency name '{}'", name);
+    }
+    Ok(())
+}
+
+/// A project's `grip.json`, declaring the CLI tools it needs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+    /// Registries searched in addition to the user's own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<Registry>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Manifest {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            name: "grip-project".to_string(),
+            version: "0.1.0".to_string(),
+            dependencies: BTreeMap::new(),
+            registries: Vec::new(),
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Find the manifest in `start` or the closest parent directory that has one
+    pub fn find(start: &Path) -> Result<Option<Self>> {
+        for dir in start.ancestors() {
+            let path = dir.join(MANIFEST_FILE);
+            if path.is_file() {
+                let content = std::fs::read_to_string(&path)?;
+                let mut manifest: Manifest = serde_json::from_str(&content)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+                for name in manifest.dependencies.keys() {
+                    check_dependency_name(name)
+                        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+                }
+                manifest.dir = dir.to_path_buf();
+                return Ok(Some(manifest));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self)?;
+        std::fs::write(self.path(), content + "\n")?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(MANIFEST_FILE)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Project tools live in `.grip` next to the manifest and are never put on the user's PATH
+    pub fn prefix(&self) -> Prefix {
+        let root = self.dir.join(".grip");
+        Prefix {
+            bin_dir: root.join("bin"),
+            root,
+            global: false,
+        }
+    }
+
+    /// The project's registries followed by the user's, a project registry replacing a user one of the same name.
+    /// Relative paths of local project registries are resolved against the project directory.
+    pub fn registries(&self, user: &[Registry]) -> Vec<Registry> {
+        let mut registries: Vec<Registry> = self.registries
+            .iter()
+            .map(|registry| match &registry.source {
+                RegistrySource::Local(path) if path.is_relative() => Registry {
+                    source: RegistrySource::Local(self.dir.join(path)),
+                    ..registry.clone()
+                },
+                _ => registry.clone(),
+            })
+            .collect();
+        registries.extend(
+            user.iter()
+                .filter(|r| !self.registries.iter().any(|own| own.name == r.name))
+                .cloned(),
+        );
+        registries
+    }
+}
+
+/// Versions pinned for a directory by the nearest `.grip-version` or `grip.json`, searching upwards
+/// from `start`. A `.grip-version` holds one `name version` or `name@version` per line.
+pub fn version_overrides(start: &Path) -> Result<Option<(PathBuf, BTreeMap<String, String>)>> {
+    for dir in start.ancestors() {
+        let path = dir.join(VERSION_FILE);
+        if path.is_file() {
+            let mut versions = BTreeMap::new();
+            for line in std::fs::read_to_string(&path)?.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let (name, version) = line
+                    .split_once('@')
+                    .or_else(|| line.split_once(char::is_whitespace))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid line in {}: {}", path.display(), line))?;
+                versions.insert(name.trim().to_string(), version.trim().to_string());
+            }
+            return Ok(Some((path, versions)));
+        }
+
+        if dir.join(MANIFEST_FILE).is_file() {
+            let manifest = Manifest::find(dir)?.unwrap_or_else(|| Manifest::new(dir));
+            let versions = manifest
+                .dependencies
+                .iter()
+                .map(|(name, dependency)| (name.clone(), dependency.version().to_string()))
+                .collect();
+            return Ok(Some((manifest.path(), versions)));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, content: &str) {
+        std::fs::write(dir.join(MANIFEST_FILE), content).unwrap();
+    }
+
+    #[test]
+    fn found_from_a_subdirectory_with_defaults_filled_in() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), r#"{ "dependencies": { "ripgrep": "^14", "protoc": { "asset": "protoc.zip" } } }"#);
+        let nested = dir.path().join("src/bin");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let manifest = Manifest::find(&nested).unwrap().unwrap();
+        assert_eq!(manifest.path(), dir.path().join(MANIFEST_FILE));
+        assert_eq!(manifest.name, "");
+        assert_eq!(manifest.dependencies["ripgrep"], Dependency::Version("^14".to_string()));
+        assert_eq!(manifest.dependencies["protoc"].version(), "latest");
+        assert_eq!(manifest.dependencies["protoc"].asset(), Some("protoc.zip"));
+        assert_eq!(manifest.prefix().bin_dir, dir.path().join(".grip/bin"));
+    }
+
+    #[test]
+    fn dependency_names_must_be_plain_components() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["..", "../evil", "a/b", ""] {
+            write(dir.path(), &format!(r#"{{ "dependencies": {{ "{}": "latest" }} }}"#, name));
+            assert!(Manifest::find(dir.path()).is_err(), "{}", name);
+        }
+        assert!(check_dependency_name("ripgrep").is_ok());
+    }
+
+    #[test]
+    fn project_registries_shadow_user_ones_of_the_same_name() {
+        let registry = |name: &str, url: &str| Registry {
+            name: name.to_string(),
+            source: url.parse().unwrap(),
+            priority: 0,
+        };
+        let mut manifest = Manifest::new(Path::new("/project"));
+        manifest.registries = vec![registry("default", "https://example.com/project.git")];
+
+        let merged = manifest.registries(&[
+            registry("default", "https://example.com/user.git"),
+            registry("extra", "https://example.com/extra.git"),
+        ]);
+        let urls: Vec<String> = merged.iter().map(|r| r.source.to_string()).collect();
+        assert_eq!(urls, ["https://example.com/project.git", "https://example.com/extra.git"]);
+    }
+
+    #[test]
+    fn pins_come_from_the_nearest_version_file_or_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), r#"{ "dependencies": { "terraform": "~1.6", "kubectl": { "version": "1.29" } } }"#);
+        let nested = dir.path().join("infra/legacy");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let (source, pins) = version_overrides(&nested).unwrap().unwrap();
+        assert_eq!(source, dir.path().join(MANIFEST_FILE));
+        assert_eq!(pins["terraform"], "~1.6");
+        assert_eq!(pins["kubectl"], "1.29");
+
+        std::fs::write(nested.join(VERSION_FILE), "# old stack\nterraform 1.5.7\n\nkubectl@1.27\n").unwrap();
+        let (source, pins) = version_overrides(&nested).unwrap().unwrap();
+        assert_eq!(source, nested.join(VERSION_FILE));
+        assert_eq!(pins["terraform"], "1.5.7");
+        assert_eq!(pins["kubectl"], "1.27");
+
+        std::fs::write(nested.join(VERSION_FILE), "terraform\n").unwrap();
+        assert!(version_overrides(&nested).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::error::Result;
+use crate::signature::SigningKeys;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -7,29 +9,34 @@ pub struct PackageInfo {
     pub name: String,
     pub repository: String,
     pub description: Option<String>,
+    /// For repositories that release several tools, the tag prefix of this one, e.g. `cli@` or `cli/v`
+    #[serde(default)]
+    pub tag_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Pinned digests by asset name, as `sha256:<hex>`, `sha512:<hex>` or bare hex
+    #[serde(default)]
+    pub checksums: HashMap<String, String>,
+    /// Public keys used to verify release signatures
+    #[serde(default)]
+    pub signing: SigningKeys,
 }
 
 pub struct Package {
     pub info: PackageInfo,
-    pub path: PathBuf,
+    /// Name of the registry the package was resolved from
+    pub registry: String,
 }
 
 impl Package {
-    pub fn load(path: PathBuf) -> Result<Self> {
+    pub fn load(path: PathBuf, registry: &str) -> Result<Self> {
         let content = std::fs::read_to_string(&path)?;
         let info: PackageInfo = serde_json::from_str(&content)?;
-        Ok(Self { info, path })
+        Ok(Self { info, registry: registry.to_string() })
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Release {
-    pub tag_name: String,
-    pub assets: Vec<Asset>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Asset {
-    pub name: String,
-    pub browser_download_url: String,
-}
\ No newline at end of file
@@ -1,6 +1,6 @@
 use crate::error::Result;
 use colored::Colorize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[cfg(windows)]
 pub async fn add_to_path(path: &Path) -> Result<()> {
@@ -75,6 +75,11 @@ pub async fn add_to_path(path: &Path) -> Result<()> {
         format!("{}/.profile", home)
     };
 
+    // Nothing to do if the directory is already on PATH, e.g. ~/.local/bin on most distributions
+    if env::var_os("PATH").is_some_and(|current| env::split_paths(&current).any(|p| p == path)) {
+        return Ok(());
+    }
+
     let export_line = format!("export PATH=\"{}:$PATH\"", path.to_string_lossy());
     
     let rc_content = std::fs::read_to_string(&shell_rc)
@@ -94,4 +99,362 @@ pub async fn add_to_path(path: &Path) -> Result<()> {
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(windows)]
+pub async fn remove_from_path(path: &Path) -> Result<()> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let environment = hkcu.open_subkey_with_flags("Environment", KEY_READ | KEY_WRITE)
+        .map_err(|e| anyhow::anyhow!("Failed to open Environment registry key: {}", e))?;
+
+    let current_path: String = environment.get_value("Path")
+        .map_err(|e| anyhow::anyhow!("Failed to get current PATH: {}", e))?;
+
+    let old_dir = path.to_string_lossy().into_owned();
+    if current_path.split(';').any(|p| p == old_dir) {
+        let new_path = current_path
+            .split(';')
+            .filter(|p| *p != old_dir)
+            .collect::<Vec<_>>()
+            .join(";");
+
+        environment.set_value("Path", &new_path)
+            .map_err(|e| anyhow::anyhow!("Failed to update PATH: {}", e))?;
+
+        println!("{} Removed from PATH: {}", "✓".green(), old_dir);
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub async fn remove_from_path(path: &Path) -> Result<()> {
+    use std::env;
+
+    let home = env::var("HOME")
+        .map_err(|_| anyhow::anyhow!("Failed to get HOME directory"))?;
+
+    let export_line = format!("export PATH=\"{}:$PATH\"", path.to_string_lossy());
+
+    // The user may have switched shells since installing, so check every rc file we could have written
+    for rc in [".bashrc", ".zshrc", ".profile"] {
+        let shell_rc = format!("{}/{}", home, rc);
+        let Ok(rc_content) = std::fs::read_to_string(&shell_rc) else {
+            continue;
+        };
+
+        let Some(new_content) = remove_export_line(&rc_content, &export_line) else {
+            continue;
+        };
+        std::fs::write(&shell_rc, new_content)?;
+
+        println!("{} Removed PATH entry from {}", "✓".green(), shell_rc);
+    }
+
+    Ok(())
+}
+
+/// Where the binary exposed as `link` in the bin directory points to, if grip created it. For a
+/// shim that is the version it runs when no pin applies.
+#[cfg(unix)]
+fn link_target(link: &Path) -> Option<PathBuf> {
+    if let Ok(target) = std::fs::read_link(link) {
+        return Some(target);
+    }
+
+    // Shims are tiny scripts, don't read whatever else sits in the bin directory
+    if link.metadata().ok()?.len() > 4096 {
+        return None;
+    }
+    shim_target(&std::fs::read_to_string(link).ok()?)
+}
+
+/// Where the binary exposed as `link` in the bin directory points to, if grip created it. For a
+/// shim that is the version it runs when no pin applies.
+#[cfg(windows)]
+fn link_target(link: &Path) -> Option<PathBuf> {
+    if link.extension().is_none_or(|ext| ext != "cmd") {
+        return None;
+    }
+
+    let content = std::fs::read_to_string(link).ok()?;
+    let line = content.lines().find(|l| l.starts_with('"'))?;
+    let target = match line.split_once("\" shim \"") {
+        Some((_, rest)) => rest.split('"').next()?,
+        None => line.trim_start_matches('"').split('"').next()?,
+    };
+    Some(PathBuf::from(target))
+}
+
+#[cfg(unix)]
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''"))
+}
+
+/// The binary a shim script written by [`create_link`] runs by default
+#[cfg(unix)]
+fn shim_target(content: &str) -> Option<PathBuf> {
+    let line = content.lines().find(|l| l.starts_with("exec "))?;
+    let (_, rest) = line.split_once(" shim '")?;
+    let quoted = rest.strip_suffix("' -- \"$@\"")?;
+    Some(PathBuf::from(quoted.replace("'\\''", "'")))
+}
+
+#[cfg(unix)]
+fn link_path(bin_dir: &Path, binary: &Path) -> Option<PathBuf> {
+    Some(bin_dir.join(binary.file_name()?))
+}
+
+#[cfg(windows)]
+fn link_path(bin_dir: &Path, binary: &Path) -> Option<PathBuf> {
+    Some(bin_dir.join(binary.file_stem()?).with_extension("cmd"))
+}
+
+/// A symlink, or with `shim` a script that runs the binary through `grip shim` so the version
+/// pinned for the current directory can take over
+#[cfg(unix)]
+fn create_link(binary: &Path, link: &Path, shim: bool) -> Result<()> {
+    if !shim {
+        return crate::utils::create_symlink(binary, link);
+    }
+
+    let grip = std::env::current_exe()?;
+    std::fs::write(
+        link,
+        format!(
+            "#!/bin/sh\n# Created by grip: runs the version pinned for the current directory, or this one\nexec {} shim {} -- \"$@\"\n",
+            shell_quote(&grip),
+            shell_quote(binary)
+        ),
+    )?;
+    crate::utils::make_executable(link)
+}
+
+/// Symlinks need elevated rights on Windows, so expose binaries through a small batch shim instead.
+/// With `shim` it goes through `grip shim` so the version pinned for the current directory can take over.
+#[cfg(windows)]
+fn create_link(binary: &Path, link: &Path, shim: bool) -> Result<()> {
+    let command = if shim {
+        format!("\"{}\" shim \"{}\" --", std::env::current_exe()?.display(), binary.display())
+    } else {
+        format!("\"{}\"", binary.display())
+    };
+    std::fs::write(link, format!("@echo off\r\n{} %*\r\n", command))?;
+    Ok(())
+}
+
+/// Split a binary installed under `packages_dir` into its package name and its path inside the
+/// version directory
+pub fn package_binary(packages_dir: &Path, binary: &Path) -> Option<(String, PathBuf)> {
+    let mut components = binary.strip_prefix(packages_dir).ok()?.components();
+    let name = components.next()?.as_os_str().to_str()?.to_string();
+    components.next()?;
+    Some((name, components.as_path().to_path_buf()))
+}
+
+/// Expose `binaries` in `bin_dir`, replacing links grip previously created into `packages_dir`.
+/// With `shims` they follow the versions pinned per directory. Returns the names of the exposed binaries.
+pub fn link_binaries(binaries: &[PathBuf], bin_dir: &Path, packages_dir: &Path, shims: bool) -> Result<Vec<String>> {
+    std::fs::create_dir_all(bin_dir)?;
+
+    let mut exposed = Vec::new();
+    for binary in binaries {
+        let Some(link) = link_path(bin_dir, binary) else {
+            continue;
+        };
+        let name = link.file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+        // Archives sometimes ship the same tool twice, only expose the first one
+        if exposed.contains(&name) {
+            continue;
+        }
+
+        if link.symlink_metadata().is_ok() {
+            match link_target(&link) {
+                Some(target) if target.starts_with(packages_dir) => std::fs::remove_file(&link)?,
+                _ => {
+                    status!(
+                        "{} Skipping {}: {} exists and was not created by grip",
+                        "!".yellow(),
+                        name,
+                        link.display()
+                    );
+                    continue;
+                }
+            }
+        }
+
+        create_link(binary, &link, shims)?;
+        exposed.push(name);
+    }
+
+    Ok(exposed)
+}
+
+/// Names of the links in `bin_dir` that point into `dir`
+pub fn linked_binaries(bin_dir: &Path, dir: &Path) -> Result<Vec<String>> {
+    let mut linked = Vec::new();
+    if !bin_dir.exists() {
+        return Ok(linked);
+    }
+
+    for entry in std::fs::read_dir(bin_dir)? {
+        let link = entry?.path();
+        if link_target(&link).is_some_and(|target| target.starts_with(dir)) {
+            linked.push(link.file_name().unwrap_or_default().to_string_lossy().into_owned());
+        }
+    }
+
+    linked.sort();
+    Ok(linked)
+}
+
+/// Remove every link in `bin_dir` that points into `dir`. Returns the names that were removed.
+pub fn unlink_binaries(bin_dir: &Path, dir: &Path) -> Result<Vec<String>> {
+    let mut removed = Vec::new();
+    if !bin_dir.exists() {
+        return Ok(removed);
+    }
+
+    for entry in std::fs::read_dir(bin_dir)? {
+        let link = entry?.path();
+        if link_target(&link).is_some_and(|target| target.starts_with(dir)) {
+            std::fs::remove_file(&link)?;
+            removed.push(link.file_name().unwrap_or_default().to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(removed)
+}
+
+/// `content` without `export_line` and the blank line `add_to_path` wrote before it,
+/// or `None` if the line isn't there
+#[cfg(unix)]
+fn remove_export_line(content: &str, export_line: &str) -> Option<String> {
+    if !content.lines().any(|line| line.trim() == export_line) {
+        return None;
+    }
+
+    let mut kept: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        if line.trim() == export_line {
+            if kept.last().is_some_and(|l| l.trim().is_empty()) {
+                kept.pop();
+            }
+        } else {
+            kept.push(line);
+        }
+    }
+
+    let mut new_content = kept.join("\n");
+    if content.ends_with('\n') {
+        new_content.push('\n');
+    }
+    Some(new_content)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_only_grips_export_line() {
+        let export = "export PATH=\"/data/grip/packages/rg/14.1.0:$PATH\"";
+        let content = format!("alias ll='ls -l'\n\n{}\nexport EDITOR=vim\n", export);
+
+        assert_eq!(
+            remove_export_line(&content, export).as_deref(),
+            Some("alias ll='ls -l'\nexport EDITOR=vim\n")
+        );
+        assert_eq!(remove_export_line("export EDITOR=vim\n", export), None);
+    }
+
+    #[test]
+    fn leaves_other_versions_alone() {
+        let export = "export PATH=\"/data/grip/packages/rg/14.1.0:$PATH\"";
+        let other = "export PATH=\"/data/grip/packages/rg/14.0.0:$PATH\"";
+        let content = format!("\n{}\n\n{}", other, export);
+
+        assert_eq!(remove_export_line(&content, export), Some(format!("\n{}", other)));
+    }
+
+    /// A packages directory with one installed version holding `binaries`
+    fn install(packages_dir: &Path, tag: &str, binaries: &[&str]) -> Vec<PathBuf> {
+        let dir = packages_dir.join("tool").join(tag);
+        std::fs::create_dir_all(&dir).unwrap();
+        binaries
+            .iter()
+            .map(|name| {
+                let binary = dir.join(name);
+                std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+                binary
+            })
+            .collect()
+    }
+
+    #[test]
+    fn links_replace_grips_own_links_but_not_foreign_files() {
+        let root = tempfile::tempdir().unwrap();
+        let (bin_dir, packages_dir) = (root.path().join("bin"), root.path().join("packages"));
+
+        let old = install(&packages_dir, "1.0.0", &["tool", "tool-helper"]);
+        assert_eq!(link_binaries(&old, &bin_dir, &packages_dir, false).unwrap(), ["tool", "tool-helper"]);
+
+        std::fs::write(bin_dir.join("other"), "not ours").unwrap();
+        let new = install(&packages_dir, "2.0.0", &["tool", "other"]);
+        assert_eq!(link_binaries(&new, &bin_dir, &packages_dir, false).unwrap(), ["tool"]);
+
+        assert_eq!(link_target(&bin_dir.join("tool")).unwrap(), new[0]);
+        assert_eq!(std::fs::read_to_string(bin_dir.join("other")).unwrap(), "not ours");
+    }
+
+    #[test]
+    fn unlinking_a_version_leaves_the_others() {
+        let root = tempfile::tempdir().unwrap();
+        let (bin_dir, packages_dir) = (root.path().join("bin"), root.path().join("packages"));
+
+        let old = install(&packages_dir, "1.0.0", &["tool", "tool-helper"]);
+        link_binaries(&old, &bin_dir, &packages_dir, false).unwrap();
+        let new = install(&packages_dir, "2.0.0", &["tool"]);
+        link_binaries(&new, &bin_dir, &packages_dir, false).unwrap();
+
+        // Only the helper still points into the old version
+        let removed = unlink_binaries(&bin_dir, &packages_dir.join("tool/1.0.0")).unwrap();
+        assert_eq!(removed, ["tool-helper"]);
+        assert!(bin_dir.join("tool").symlink_metadata().is_ok());
+        assert!(bin_dir.join("tool-helper").symlink_metadata().is_err());
+    }
+
+    #[test]
+    fn shims_are_recognised_as_grips_own() {
+        let root = tempfile::tempdir().unwrap();
+        let (bin_dir, packages_dir) = (root.path().join("bin"), root.path().join("it's packages"));
+
+        let old = install(&packages_dir, "1.0.0", &["tool"]);
+        link_binaries(&old, &bin_dir, &packages_dir, true).unwrap();
+        let shim = bin_dir.join("tool");
+        assert!(std::fs::read_link(&shim).is_err());
+        assert!(crate::utils::is_binary(&shim));
+        assert_eq!(link_target(&shim).unwrap(), old[0]);
+
+        // A new version replaces the shim, and unlinking the old one leaves it in place
+        let new = install(&packages_dir, "2.0.0", &["tool"]);
+        link_binaries(&new, &bin_dir, &packages_dir, true).unwrap();
+        assert_eq!(link_target(&shim).unwrap(), new[0]);
+        assert!(unlink_binaries(&bin_dir, &packages_dir.join("tool/1.0.0")).unwrap().is_empty());
+        assert_eq!(unlink_binaries(&bin_dir, &packages_dir.join("tool/2.0.0")).unwrap(), ["tool"]);
+    }
+
+    #[test]
+    fn binaries_split_into_package_and_path_in_the_version() {
+        let packages_dir = Path::new("/data/grip/packages");
+        assert_eq!(
+            package_binary(packages_dir, &packages_dir.join("terraform/v1.5.7/bin/terraform")),
+            Some(("terraform".to_string(), PathBuf::from("bin/terraform")))
+        );
+        assert_eq!(package_binary(packages_dir, Path::new("/usr/bin/terraform")), None);
+    }
+}
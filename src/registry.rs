@@ -1,131 +1,596 @@
+use crate::backend;
+use crate::checksum::{Checksum, StreamHasher};
 use crate::error::{Result, GripError};
 use crate::package::Package;
 use crate::config::Registry;
+use crate::source::RegistrySource;
+use chrono::{DateTime, Utc};
 use colored::Colorize;
-use tokio::process::Command;
+use std::collections::HashMap;
 use tokio::io::AsyncWriteExt;
 use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
 use std::path::PathBuf;
 
+/// A downloaded release asset and the SHA-256 of its contents
+pub struct DownloadedAsset {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// When registry checkouts are brought up to date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Pull registries last synced more than `ttl` seconds ago
+    Auto { ttl: u64 },
+    /// Only clone missing registries; pull on `grip registry update`
+    Manual,
+    /// Pull every registry before using it
+    Refresh,
+    /// Never touch the network for registries
+    Offline,
+}
+
 pub struct RegistryManager {
     pub data_dir: PathBuf,
+    sync: SyncPolicy,
+    /// How many registries are synced at once
+    sync_concurrency: usize,
     client: reqwest::Client,
+    /// Used for authenticated asset downloads, where redirects are followed by hand
+    api_client: reqwest::Client,
+    github_token: Option<String>,
+    /// Root of the GitHub REST API, overridden in tests
+    api_base: String,
 }
 
 impl RegistryManager {
-    pub fn new(data_dir: PathBuf) -> Self {
+    pub fn new(data_dir: PathBuf, github_token: Option<String>, sync: SyncPolicy, sync_concurrency: usize) -> Self {
         Self {
             data_dir,
+            sync,
+            sync_concurrency,
             client: reqwest::Client::new(),
+            api_client: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("failed to build HTTP client"),
+            github_token,
+            api_base: "https://api.github.com".to_string(),
         }
     }
 
-    pub async fn ensure_registry(&self, registry: &Registry) -> Result<PathBuf> {
-        let registry_path = self.data_dir.join("registries").join(&registry.name);
-        
-        if !registry_path.exists() {
-            println!("{} Cloning registry {}...", "→".blue(), registry.name.cyan());
-            let url = format!("https://{}.git", registry.url);
-            let status = Command::new("git")
-                .args(["clone", "--depth", "1", &url, &registry_path.to_string_lossy()])
-                .status()
-                .await?;
-
-            if !status.success() {
-                return Err(GripError::RegistryError(format!(
-                    "Failed to clone registry {}", 
-                    registry.name
-                )).into());
-            }
-        } else {
-            println!("{} Updating registry {}...", "→".blue(), registry.name.cyan());
-            let status = Command::new("git")
-                .args(["pull", "--ff-only"])
-                .current_dir(&registry_path)
-                .status()
-                .await?;
-
-            if !status.success() {
-                return Err(GripError::RegistryError(format!(
-                    "Failed to update registry {}", 
-                    registry.name
-                )).into());
-            }
+    /// GET a GitHub API endpoint, authenticated when a token is available
+    async fn github_get(&self, url: &str) -> Result<reqwest::Response> {
+        let mut request = self.client
+            .get(url)
+            .header("User-Agent", "grip")
+            .header("Accept", "application/vnd.github+json");
+
+        if let Some(token) = &self.github_token {
+            request = request.bearer_auth(token);
         }
 
-        Ok(registry_path)
+        let response = request.send().await?;
+        self.check_rate_limit(&response)?;
+        Ok(response)
     }
 
-    pub async fn find_package(&self, registries: &[Registry], package_name: &str) -> Result<Package> {
-        // Sort registries by priority (highest first)
+    /// Turn GitHub's rate limit responses into a readable error, and warn when running low
+    fn check_rate_limit(&self, response: &reqwest::Response) -> Result<()> {
+        let header = |name: &str| -> Option<u64> {
+            response.headers().get(name)?.to_str().ok()?.parse().ok()
+        };
+
+        let Some(remaining) = header("x-ratelimit-remaining") else {
+            return Ok(());
+        };
+        let limit = header("x-ratelimit-limit").unwrap_or(0);
+        let reset = header("x-ratelimit-reset")
+            .and_then(|epoch| chrono::DateTime::from_timestamp(epoch as i64, 0))
+            .map(|time| time.with_timezone(&chrono::Local));
+
+        let status = response.status();
+        let exhausted = remaining == 0
+            && (status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::TOO_MANY_REQUESTS);
+
+        if exhausted {
+            let reset_at = reset
+                .map(|time| {
+                    let minutes = (time - chrono::Local::now()).num_minutes().max(0);
+                    format!("resets at {} (in {} min)", time.format("%H:%M"), minutes)
+                })
+                .unwrap_or_else(|| "reset time unknown".to_string());
+
+            let hint = if self.github_token.is_none() {
+                "; set GITHUB_TOKEN or run 'gh auth login' to raise the limit"
+            } else {
+                ""
+            };
+
+            return Err(GripError::RateLimited(format!(
+                "{} requests per hour used up, {}{}",
+                limit, reset_at, hint
+            )).into());
+        }
+
+        if remaining > 0 && remaining <= 5 {
+            status!(
+                "{} Only {} GitHub API requests left this hour",
+                "!".yellow(),
+                remaining
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Whether a registry has to be cloned or pulled before use under the sync policy
+    fn needs_sync(&self, registry: &Registry) -> bool {
+        // Local registries are read in place, syncing only reports one that went missing
+        if matches!(registry.source, RegistrySource::Local(_)) {
+            return !self.registry_path(registry).join("packages").is_dir();
+        }
+
+        if !self.checkout_path(registry).exists() {
+            return self.sync != SyncPolicy::Offline;
+        }
+
+        match self.sync {
+            SyncPolicy::Offline | SyncPolicy::Manual => false,
+            SyncPolicy::Refresh => true,
+            SyncPolicy::Auto { ttl } => self.last_synced(registry).is_none_or(|synced| {
+                chrono::Utc::now().signed_duration_since(synced).num_seconds() >= ttl as i64
+            }),
+        }
+    }
+
+    /// Sync whatever the policy asks for, then return the usable local checkouts, highest priority
+    /// first. A registry that fails to sync is used as last synced, or skipped if it was never cloned.
+    pub async fn prepare_registries(&self, registries: &[Registry]) -> Result<Vec<(Registry, PathBuf)>> {
         let mut sorted_registries = registries.to_vec();
-        sorted_registries.sort_by(|a, b| b.priority.cmp(&a.priority));
+        sorted_registries.sort_by_key(|r| std::cmp::Reverse(r.priority));
 
+        let stale: Vec<Registry> = sorted_registries
+            .iter()
+            .filter(|r| self.needs_sync(r))
+            .cloned()
+            .collect();
+        let failures = self.sync_registries(&stale).await;
+
+        let mut ready = Vec::new();
         for registry in sorted_registries {
-            let registry_path = self.ensure_registry(&registry).await?;
-            let packages_path = registry_path.join("packages");
-            
-            if packages_path.exists() {
-                let package_file = packages_path.join(format!("{}.json", package_name));
-                if package_file.exists() {
-                    return Package::load(package_file);
+            let registry_path = self.registry_path(&registry);
+            let usable = registry_path.join("packages").is_dir();
+
+            match failures.iter().find(|(name, _)| *name == registry.name) {
+                Some((_, err)) if usable => eprintln!(
+                    "{} Could not update registry {}, using the last synced copy: {:#}",
+                    "!".yellow(), registry.name, err
+                ),
+                Some((_, err)) => eprintln!(
+                    "{} Skipping registry {}: {:#}",
+                    "!".yellow(), registry.name, err
+                ),
+                None if !usable => eprintln!(
+                    "{} Skipping registry {}: never synced and --offline was given",
+                    "!".yellow(), registry.name
+                ),
+                None => {}
+            }
+
+            if usable {
+                ready.push((registry, registry_path));
+            }
+        }
+
+        if ready.is_empty() && !registries.is_empty() {
+            return Err(GripError::RegistryError("No registry is available".to_string()).into());
+        }
+        Ok(ready)
+    }
+
+    /// Identifies a registry's copy and sync state. The source is part of it, so registries
+    /// sharing a name (a project's and the user's, or two projects') never share a checkout.
+    fn checkout_key(registry: &Registry) -> String {
+        let digest = format!("{:x}", Sha256::digest(registry.source.to_string().as_bytes()));
+        format!("{}-{}", registry.name, &digest[..12])
+    }
+
+    /// Where a registry is synced to: the directory itself for local registries, a copy otherwise
+    fn checkout_path(&self, registry: &Registry) -> PathBuf {
+        match &registry.source {
+            RegistrySource::Local(path) => path.clone(),
+            _ => self.data_dir.join("registries").join(Self::checkout_key(registry)),
+        }
+    }
+
+    /// Delete a registry's synced copy and sync state. Local registries are left alone.
+    pub fn remove_checkout(&self, registry: &Registry) -> Result<()> {
+        if !matches!(registry.source, RegistrySource::Local(_)) {
+            let checkout_path = self.checkout_path(registry);
+            if checkout_path.exists() {
+                std::fs::remove_dir_all(checkout_path)?;
+            }
+        }
+        self.remove_legacy_checkout(registry);
+
+        let mut state = self.load_sync_state();
+        let removed = state.remove(&Self::checkout_key(registry)).is_some();
+        if state.remove(&registry.name).is_some() || removed {
+            self.save_sync_state(&state)?;
+        }
+        Ok(())
+    }
+
+    /// Copies used to be kept in `registries/<name>`, which no key ever matches. Once the registry
+    /// has a keyed copy the old one is dead weight; failing to delete it is not worth an error.
+    fn remove_legacy_checkout(&self, registry: &Registry) {
+        let legacy_path = self.data_dir.join("registries").join(&registry.name);
+        if legacy_path.is_dir() {
+            let _ = std::fs::remove_dir_all(legacy_path);
+        }
+    }
+
+    /// The directory holding a registry's `packages/`
+    fn registry_path(&self, registry: &Registry) -> PathBuf {
+        let checkout = self.checkout_path(registry);
+        match &registry.source {
+            RegistrySource::Git(git) => match &git.subdir {
+                Some(subdir) => checkout.join(subdir),
+                None => checkout,
+            },
+            _ => checkout,
+        }
+    }
+
+    /// Clone or pull registries concurrently, at most `sync_concurrency` at a time, whatever the
+    /// sync policy. Returns the registries that failed along with why.
+    pub async fn sync_registries(&self, registries: &[Registry]) -> Vec<(String, anyhow::Error)> {
+        if registries.is_empty() {
+            return Vec::new();
+        }
+
+        // Progress goes to stderr so commands with machine readable output stay clean
+        let progress = indicatif::MultiProgress::new();
+        let style = indicatif::ProgressStyle::default_spinner()
+            .template("{spinner:.green} {msg}")
+            .unwrap();
+
+        let results: Vec<(&Registry, Result<()>)> = futures_util::stream::iter(registries)
+            .map(|registry| {
+                let bar = progress.add(indicatif::ProgressBar::new_spinner());
+                bar.set_style(style.clone());
+                bar.enable_steady_tick(std::time::Duration::from_millis(100));
+
+                async move {
+                    let result = self.sync_registry(registry, &bar).await;
+                    match &result {
+                        Ok(()) => bar.finish_with_message(format!("{} Synced registry {}", "✓".green(), registry.name.cyan())),
+                        Err(_) => bar.finish_with_message(format!("{} Failed to sync registry {}", "✗".red(), registry.name.cyan())),
+                    }
+                    (registry, result)
                 }
+            })
+            .buffer_unordered(self.sync_concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut synced = Vec::new();
+        let mut failures = Vec::new();
+        for (registry, result) in results {
+            match result {
+                Ok(()) => {
+                    self.remove_legacy_checkout(registry);
+                    synced.push(registry);
+                }
+                Err(err) => failures.push((registry.name.clone(), err)),
+            }
+        }
+
+        if let Err(err) = self.record_sync(&synced) {
+            failures.push(("sync state".to_string(), err));
+        }
+        failures
+    }
+
+    async fn sync_registry(&self, registry: &Registry, bar: &indicatif::ProgressBar) -> Result<()> {
+        let checkout_path = self.checkout_path(registry);
+        std::fs::create_dir_all(self.data_dir.join("registries"))?;
+        backend::for_registry(registry, &self.client)
+            .sync(&checkout_path, bar)
+            .await
+    }
+
+    fn sync_state_path(&self) -> PathBuf {
+        self.data_dir.join("registry-sync.json")
+    }
+
+    /// When each registry was last synced, by checkout key
+    fn load_sync_state(&self) -> HashMap<String, DateTime<Utc>> {
+        std::fs::read_to_string(self.sync_state_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn last_synced(&self, registry: &Registry) -> Option<DateTime<Utc>> {
+        self.load_sync_state().get(&Self::checkout_key(registry)).copied()
+    }
+
+    /// Stamp registries as synced now, dropping any sync time still stored under the bare name
+    fn record_sync(&self, registries: &[&Registry]) -> Result<()> {
+        if registries.is_empty() {
+            return Ok(());
+        }
+
+        let mut state = self.load_sync_state();
+        let now = chrono::Utc::now();
+        for registry in registries {
+            state.remove(&registry.name);
+            state.insert(Self::checkout_key(registry), now);
+        }
+        self.save_sync_state(&state)
+    }
+
+    /// Write the sync state through a temporary file so a crash never leaves it half written
+    fn save_sync_state(&self, state: &HashMap<String, DateTime<Utc>>) -> Result<()> {
+        let path = self.sync_state_path();
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(state)?)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    pub async fn find_package(&self, registries: &[Registry], package_name: &str) -> Result<Package> {
+        for (registry, registry_path) in self.prepare_registries(registries).await? {
+            let package_file = registry_path
+                .join("packages")
+                .join(format!("{}.json", package_name));
+            if package_file.exists() {
+                return Package::load(package_file, &registry.name);
             }
         }
 
         Err(GripError::PackageNotFound(package_name.to_string()).into())
     }
 
-    pub async fn get_releases(&self, repo: &str) -> Result<Vec<serde_json::Value>> {
-        let releases_url = format!(
-            "https://api.github.com/repos/{}/releases",
-            repo
-        );
+    /// Every package definition in the given registries, highest priority registry first
+    pub async fn list_packages(&self, registries: &[Registry]) -> Result<Vec<Package>> {
+        let mut packages = Vec::new();
+        for (registry, registry_path) in self.prepare_registries(registries).await? {
+            let packages_path = registry_path.join("packages");
+            if !packages_path.exists() {
+                continue;
+            }
 
-        let response = self.client
-            .get(&releases_url)
-            .header("User-Agent", "grip")
-            .send()
-            .await?;
+            let mut files: Vec<PathBuf> = std::fs::read_dir(&packages_path)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            files.sort();
 
-        if !response.status().is_success() {
-            if response.status() == 404 {
-                return Err(GripError::RegistryError(format!(
-                    "Repository '{}' not found on GitHub", 
-                    repo
-                )).into());
+            for file in files {
+                match Package::load(file.clone(), &registry.name) {
+                    Ok(package) => packages.push(package),
+                    Err(e) => eprintln!(
+                        "{} Skipping {} in registry {}: {}",
+                        "!".yellow(),
+                        file.file_name().unwrap_or_default().to_string_lossy(),
+                        registry.name,
+                        e
+                    ),
+                }
             }
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(GripError::RegistryError(format!(
-                "GitHub API error ({}): {}", 
-                status, error_text
-            )).into());
         }
 
-        let releases = response
+        Ok(packages)
+    }
+
+    /// Turn an unsuccessful releases API response into an error
+    async fn releases_error(&self, repo: &str, response: reqwest::Response) -> anyhow::Error {
+        if response.status() == 404 {
+            let hint = if self.github_token.is_none() {
+                " (private repositories need GITHUB_TOKEN)"
+            } else {
+                ""
+            };
+            return GripError::RegistryError(format!(
+                "Repository '{}' not found on GitHub{}",
+                repo, hint
+            )).into();
+        }
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        GripError::RegistryError(format!(
+            "GitHub API error ({}): {}",
+            status, error_text
+        )).into()
+    }
+
+    async fn parse_release<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+        Ok(response
             .json()
             .await
             .map_err(|e| GripError::RegistryError(format!(
-                "Failed to parse GitHub releases: {}", 
+                "Failed to parse GitHub releases: {}",
                 e
-            )))?;
+            )))?)
+    }
+
+    /// URL of the first page of a repository's releases
+    pub fn releases_url(&self, repo: &str, per_page: u32) -> String {
+        format!("{}/repos/{}/releases?per_page={}", self.api_base, repo, per_page)
+    }
 
+    /// One page of releases, newest first, and the URL of the next page if there is one.
+    /// Drafts are always left out, prereleases unless `include_prereleases` is set.
+    pub async fn get_releases_page(
+        &self,
+        repo: &str,
+        url: &str,
+        include_prereleases: bool,
+    ) -> Result<(Vec<serde_json::Value>, Option<String>)> {
+        let response = self.github_get(url).await?;
+        if !response.status().is_success() {
+            return Err(self.releases_error(repo, response).await);
+        }
+
+        let next = next_page(response.headers());
+        let mut releases: Vec<serde_json::Value> = Self::parse_release(response).await?;
+        releases.retain(|r| {
+            !r["draft"].as_bool().unwrap_or(false)
+                && (include_prereleases || !r["prerelease"].as_bool().unwrap_or(false))
+        });
+        Ok((releases, next))
+    }
+
+    /// All published releases of a repository, newest first, following every page
+    pub async fn get_releases(&self, repo: &str, include_prereleases: bool) -> Result<Vec<serde_json::Value>> {
+        let mut next = Some(self.releases_url(repo, 100));
+        let mut releases = Vec::new();
+
+        while let Some(url) = next {
+            let (page, more) = self.get_releases_page(repo, &url, include_prereleases).await?;
+            releases.extend(page);
+            next = more;
+        }
         Ok(releases)
     }
 
+    /// Look up a single release by its exact tag
+    pub async fn get_release_by_tag(&self, repo: &str, tag: &str) -> Result<Option<serde_json::Value>> {
+        let url = format!("{}/repos/{}/releases/tags/{}", self.api_base, repo, encode_path_segment(tag));
+        let response = self.github_get(&url).await?;
+
+        // A 404 here means the tag has no release, unless the repository itself is missing
+        if response.status() == 404 {
+            let repo_url = format!("{}/repos/{}", self.api_base, repo);
+            let repo_response = self.github_get(&repo_url).await?;
+            if !repo_response.status().is_success() {
+                return Err(self.releases_error(repo, repo_response).await);
+            }
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(self.releases_error(repo, response).await);
+        }
+
+        Self::parse_release(response).await.map(Some)
+    }
+
+    /// The newest release that is neither a draft nor a prerelease
+    pub async fn get_latest_release(&self, repo: &str) -> Result<Option<serde_json::Value>> {
+        let url = format!("{}/repos/{}/releases/latest", self.api_base, repo);
+        let response = self.github_get(&url).await?;
+
+        if response.status() == 404 {
+            // Either no stable release exists or the repository is missing; the list tells them apart
+            return Ok(self.get_releases(repo, false).await?.into_iter().next());
+        }
+        if !response.status().is_success() {
+            return Err(self.releases_error(repo, response).await);
+        }
+
+        Self::parse_release(response).await.map(Some)
+    }
+
+    /// Start downloading a release asset. With a token the asset API is used, which also works for
+    /// private repositories. It answers with a redirect to a pre-signed storage URL; that request is
+    /// made without the token so it never leaves api.github.com.
+    async fn request_asset(&self, asset: &serde_json::Value) -> Result<reqwest::Response> {
+        let name = asset["name"].as_str().unwrap_or("asset");
+
+        let response = match (&self.github_token, asset["url"].as_str()) {
+            (Some(token), Some(api_url)) => {
+                let response = self.api_client
+                    .get(api_url)
+                    .header("User-Agent", "grip")
+                    .header("Accept", "application/octet-stream")
+                    .bearer_auth(token)
+                    .send()
+                    .await?;
+                self.check_rate_limit(&response)?;
+
+                if response.status().is_redirection() {
+                    let location = response
+                        .headers()
+                        .get(reqwest::header::LOCATION)
+                        .and_then(|value| value.to_str().ok())
+                        .ok_or_else(|| GripError::DownloadError(format!(
+                            "GitHub redirected the download of {} without a location",
+                            name
+                        )))?;
+                    let target = response.url().join(location).map_err(|e| GripError::DownloadError(format!(
+                        "Invalid redirect for {}: {}",
+                        name, e
+                    )))?;
+
+                    self.client
+                        .get(target)
+                        .header("User-Agent", "grip")
+                        .send()
+                        .await?
+                } else {
+                    response
+                }
+            }
+            _ => {
+                let url = asset["browser_download_url"].as_str().ok_or_else(|| {
+                    GripError::DownloadError(format!("No download URL for {}", name))
+                })?;
+                self.client
+                    .get(url)
+                    .header("User-Agent", "grip")
+                    .send()
+                    .await?
+            }
+        };
+
+        if !response.status().is_success() {
+            let hint = if response.status() == reqwest::StatusCode::NOT_FOUND && self.github_token.is_none() {
+                "; if the repository is private, set GITHUB_TOKEN or run 'gh auth login'"
+            } else {
+                ""
+            };
+            return Err(GripError::DownloadError(format!(
+                "Failed to download {} ({}){}",
+                name,
+                response.status(),
+                hint
+            )).into());
+        }
+
+        Ok(response)
+    }
+
+    /// Fetch a small release asset such as a checksum list or signature
+    pub async fn fetch_asset(&self, asset: &serde_json::Value) -> Result<Vec<u8>> {
+        let response = self.request_asset(asset).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    pub async fn fetch_asset_text(&self, asset: &serde_json::Value) -> Result<String> {
+        let bytes = self.fetch_asset(asset).await?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Download an asset into `target_dir`. When `expected` is given the contents are verified
+    /// while streaming and nothing is written to `target_dir` on a mismatch.
     pub async fn download_asset(
-        &self, 
-        url: &str, 
-        filename: &str,
-        target_dir: &PathBuf
-    ) -> Result<PathBuf> {
-        println!("{} Downloading {}", "→".blue(), filename.cyan());
-
-        let response = self.client
-            .get(url)
-            .send()
-            .await?;
+        &self,
+        asset: &serde_json::Value,
+        target_dir: &PathBuf,
+        expected: Option<&Checksum>,
+    ) -> Result<DownloadedAsset> {
+        let filename = asset["name"]
+            .as_str()
+            .ok_or_else(|| GripError::AssetNotFound("Asset has no name".to_string()))?;
+
+        status!("{} Downloading {}", "→".blue(), filename.cyan());
+
+        let response = self.request_asset(asset).await?;
 
         let total_size = response.content_length().unwrap_or(0);
         
@@ -142,16 +607,31 @@ impl RegistryManager {
         let mut file = tokio::fs::File::create(&temp_path).await?;
         let mut stream = response.bytes_stream();
         let mut downloaded = 0u64;
+        let mut hasher = StreamHasher::new(expected);
 
         while let Some(chunk_result) = stream.next().await {
             let chunk = chunk_result?;
+            hasher.update(&chunk);
             file.write_all(&chunk).await?;
             downloaded += chunk.len() as u64;
             pb.set_position(downloaded);
         }
 
+        file.flush().await?;
         pb.finish_with_message("Download complete!");
 
+        let (sha256, actual) = hasher.finalize(expected);
+        if let (Some(expected), Some(actual)) = (expected, actual) {
+            if expected.hex != actual {
+                // The temporary directory, and the file in it, is removed when it goes out of scope
+                return Err(GripError::ChecksumMismatch(format!(
+                    "{}: expected {}, got {}:{}",
+                    filename, expected, expected.algorithm, actual
+                )).into());
+            }
+            status!("{} Verified {} checksum", "✓".green(), expected.algorithm);
+        }
+
         // Create target directory if it doesn't exist
         if !target_dir.exists() {
             tokio::fs::create_dir_all(target_dir).await?;
@@ -161,6 +641,377 @@ impl RegistryManager {
         tokio::fs::copy(&temp_path, &final_path).await?;
         tokio::fs::remove_file(&temp_path).await?;
 
-        Ok(final_path)
+        Ok(DownloadedAsset {
+            path: final_path,
+            sha256,
+        })
+    }
+}
+
+/// URL of the next page from a GitHub `Link` header, e.g. `<https://...&page=2>; rel="next", <...>; rel="last"`
+fn next_page(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// Percent-encode everything but unreserved characters, so a tag like `v1.0+build/2` stays one path segment
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// A release's tag with its publish date and whether it is a prerelease, e.g. `v1.2.0 (2024-03-01) [prerelease]`
+pub fn describe_release(release: &serde_json::Value) -> String {
+    let mut text = release["tag_name"].as_str().unwrap_or("unknown").to_string();
+    if let Some(date) = release["published_at"].as_str().and_then(|d| d.get(..10)) {
+        text.push_str(&format!(" ({})", date));
+    }
+    if release["prerelease"].as_bool().unwrap_or(false) {
+        text.push_str(" [prerelease]");
+    }
+    text
+}
+
+/// Index of the newest release that is neither a draft nor a prerelease. GitHub lists releases newest first.
+pub fn latest_stable(releases: &[serde_json::Value]) -> Option<usize> {
+    releases.iter().position(|r| {
+        !r["draft"].as_bool().unwrap_or(false) && !r["prerelease"].as_bool().unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Response, Server};
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn api_requests_carry_the_token() {
+        let server = Server::start(|_| Response::new(200, "[]")).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let manager = RegistryManager::new(dir.path().to_path_buf(), Some("secret".to_string()), SyncPolicy::Offline, 4);
+        manager.github_get(&server.url("/repos/o/r/releases")).await.unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Offline, 4);
+        manager.github_get(&server.url("/repos/o/r/releases")).await.unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0].head.contains("authorization: bearer secret"));
+        assert!(!requests[1].has_header("authorization"));
+        assert!(requests[1].head.contains("user-agent: grip"));
+    }
+
+    #[tokio::test]
+    async fn exhausted_rate_limit_is_reported() {
+        let server = Server::start(|_| {
+            Response::new(403, "{}")
+                .header("x-ratelimit-limit", "60")
+                .header("x-ratelimit-remaining", "0")
+                .header("x-ratelimit-reset", "4102444800")
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Offline, 4);
+
+        let err = manager.github_get(&server.url("/repos/o/r/releases")).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<GripError>(), Some(GripError::RateLimited(_))));
+        assert!(err.to_string().contains("60 requests per hour"), "{}", err);
+        assert!(err.to_string().contains("GITHUB_TOKEN"), "{}", err);
+    }
+
+    fn asset(server: &Server) -> serde_json::Value {
+        serde_json::json!({
+            "name": "tool.tar.gz",
+            "url": server.url("/api/assets/1"),
+            "browser_download_url": server.url("/download/tool.tar.gz"),
+        })
+    }
+
+    #[tokio::test]
+    async fn private_assets_go_through_the_api_and_drop_the_token_on_redirect() {
+        let server = Server::start(|request| match request.path.as_str() {
+            "/api/assets/1" => Response::new(302, "").header("Location", "/storage/tool.tar.gz?signed=1"),
+            "/storage/tool.tar.gz?signed=1" => Response::new(200, "contents"),
+            _ => Response::new(404, ""),
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), Some("secret".to_string()), SyncPolicy::Offline, 4);
+
+        assert_eq!(manager.fetch_asset(&asset(&server)).await.unwrap(), b"contents");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].head.contains("authorization: bearer secret"));
+        assert!(requests[0].head.contains("accept: application/octet-stream"));
+        assert_eq!(requests[1].path, "/storage/tool.tar.gz?signed=1");
+        assert!(!requests[1].has_header("authorization"));
+    }
+
+    #[tokio::test]
+    async fn public_assets_use_the_browser_url_and_hint_at_tokens() {
+        let server = Server::start(|request| match request.path.as_str() {
+            "/download/tool.tar.gz" => Response::new(200, "contents"),
+            _ => Response::new(404, ""),
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Offline, 4);
+
+        assert_eq!(manager.fetch_asset(&asset(&server)).await.unwrap(), b"contents");
+        assert_eq!(server.requests()[0].path, "/download/tool.tar.gz");
+
+        let mut missing = asset(&server);
+        missing["browser_download_url"] = server.url("/download/gone.tar.gz").into();
+        let err = manager.fetch_asset(&missing).await.unwrap_err();
+        assert!(err.to_string().contains("GITHUB_TOKEN"), "{}", err);
     }
-}
\ No newline at end of file
+
+    fn registry(name: &str, url: &str, priority: i32) -> Registry {
+        Registry {
+            name: name.to_string(),
+            source: url.parse().unwrap(),
+            priority,
+        }
+    }
+
+    #[test]
+    fn registries_are_synced_once_the_ttl_has_passed() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Auto { ttl: 3600 }, 4);
+        let default = registry("default", "github.com/example/registry", 0);
+        assert!(manager.needs_sync(&default));
+
+        std::fs::create_dir_all(manager.registry_path(&default)).unwrap();
+        assert!(manager.needs_sync(&default));
+        manager.record_sync(&[&default]).unwrap();
+        assert!(!manager.needs_sync(&default));
+
+        let mut state = manager.load_sync_state();
+        state.insert(RegistryManager::checkout_key(&default), Utc::now() - chrono::Duration::hours(2));
+        manager.save_sync_state(&state).unwrap();
+        assert!(manager.needs_sync(&default));
+
+        manager.remove_checkout(&default).unwrap();
+        assert_eq!(manager.last_synced(&default), None);
+        assert!(!manager.registry_path(&default).exists());
+    }
+
+    #[test]
+    fn copies_and_sync_times_kept_under_the_bare_name_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Manual, 4);
+        let default = registry("default", "github.com/example/registry", 0);
+        let legacy_path = dir.path().join("registries/default");
+        assert_ne!(manager.registry_path(&default), legacy_path);
+
+        let mut state = manager.load_sync_state();
+        state.insert("default".to_string(), Utc::now());
+        manager.save_sync_state(&state).unwrap();
+        manager.record_sync(&[&default]).unwrap();
+        assert_eq!(manager.load_sync_state().len(), 1);
+        assert!(manager.last_synced(&default).is_some());
+
+        std::fs::create_dir_all(legacy_path.join("packages")).unwrap();
+        manager.remove_checkout(&default).unwrap();
+        assert!(!legacy_path.exists());
+        assert!(manager.load_sync_state().is_empty());
+    }
+
+    #[test]
+    fn sync_policies_other_than_auto_ignore_the_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = |sync| RegistryManager::new(dir.path().to_path_buf(), None, sync, 4);
+        let synced = registry("synced", "github.com/example/registry", 0);
+        let missing = registry("missing", "github.com/example/other", 0);
+        std::fs::create_dir_all(policy(SyncPolicy::Offline).registry_path(&synced)).unwrap();
+        policy(SyncPolicy::Offline).record_sync(&[&synced]).unwrap();
+
+        assert!(policy(SyncPolicy::Refresh).needs_sync(&synced));
+        assert!(!policy(SyncPolicy::Manual).needs_sync(&synced));
+        assert!(!policy(SyncPolicy::Offline).needs_sync(&synced));
+
+        // Missing checkouts are cloned unless offline
+        assert!(policy(SyncPolicy::Manual).needs_sync(&missing));
+        assert!(!policy(SyncPolicy::Offline).needs_sync(&missing));
+    }
+
+    #[tokio::test]
+    async fn failed_syncs_fall_back_to_the_last_copy_or_skip_the_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Refresh, 4);
+
+        // Pulling fails in a directory that isn't a git checkout, cloning fails against a closed port
+        let stale = registry("stale", "http://127.0.0.1:9/stale.git", 10);
+        let broken = registry("broken", "http://127.0.0.1:9/broken.git", 20);
+        std::fs::create_dir_all(manager.registry_path(&stale).join("packages")).unwrap();
+
+        let ready = manager.prepare_registries(&[stale.clone(), broken.clone()]).await.unwrap();
+        let names: Vec<&str> = ready.iter().map(|(r, _)| r.name.as_str()).collect();
+        assert_eq!(names, ["stale"]);
+        assert_eq!(manager.last_synced(&stale), None);
+
+        let err = manager.prepare_registries(&[broken]).await.unwrap_err();
+        assert!(err.to_string().contains("No registry is available"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn offline_skips_registries_that_were_never_synced() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Offline, 4);
+
+        let err = manager
+            .prepare_registries(&[registry("default", "github.com/example/registry", 0)])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No registry is available"), "{}", err);
+    }
+
+    #[test]
+    fn releases_are_described_with_date_and_prerelease_flag() {
+        let release = serde_json::json!({ "tag_name": "v1.2.0", "published_at": "2024-03-01T12:00:00Z" });
+        assert_eq!(describe_release(&release), "v1.2.0 (2024-03-01)");
+
+        let release = serde_json::json!({ "tag_name": "v2.0.0-rc.1", "prerelease": true });
+        assert_eq!(describe_release(&release), "v2.0.0-rc.1 [prerelease]");
+        assert_eq!(describe_release(&serde_json::json!({})), "unknown");
+    }
+
+    #[test]
+    fn tags_are_encoded_as_one_path_segment() {
+        assert_eq!(encode_path_segment("v1.2.3"), "v1.2.3");
+        assert_eq!(encode_path_segment("cli/v1.0+build#2"), "cli%2Fv1.0%2Bbuild%232");
+    }
+
+    #[test]
+    fn next_page_comes_from_the_link_header() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(next_page(&headers), None);
+
+        headers.insert(
+            reqwest::header::LINK,
+            "<https://api.github.com/x?page=3>; rel=\"next\", <https://api.github.com/x?page=9>; rel=\"last\""
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(next_page(&headers).as_deref(), Some("https://api.github.com/x?page=3"));
+
+        headers.insert(reqwest::header::LINK, "<https://api.github.com/x?page=1>; rel=\"prev\"".parse().unwrap());
+        assert_eq!(next_page(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn releases_are_read_page_by_page_without_drafts() {
+        let server = Server::start(|request| match request.path.as_str() {
+            "/repos/o/r/releases?per_page=100" => Response::new(
+                200,
+                r#"[{"tag_name": "v3", "draft": true}, {"tag_name": "v2", "prerelease": true}]"#,
+            )
+            .header("Link", "</repos/o/r/releases?page=2>; rel=\"next\""),
+            "/repos/o/r/releases?page=2" => Response::new(200, r#"[{"tag_name": "v1"}]"#),
+            _ => Response::new(404, ""),
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Offline, 4);
+        manager.api_base = server.base.clone();
+
+        let (first, next) = manager
+            .get_releases_page("o/r", &manager.releases_url("o/r", 100), true)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0]["tag_name"], "v2");
+        assert_eq!(next.as_deref(), Some("/repos/o/r/releases?page=2"));
+        assert_eq!(server.requests().len(), 1);
+
+        let (second, next) = manager
+            .get_releases_page("o/r", &server.url("/repos/o/r/releases?page=2"), true)
+            .await
+            .unwrap();
+        assert_eq!(second[0]["tag_name"], "v1");
+        assert_eq!(next, None);
+    }
+
+    #[tokio::test]
+    async fn tags_with_slashes_are_looked_up_encoded() {
+        let server = Server::start(|request| match request.path.as_str() {
+            "/repos/o/r/releases/tags/cli%2Fv1.0%2Bbuild" => Response::new(200, r#"{"tag_name": "cli/v1.0+build"}"#),
+            _ => Response::new(404, ""),
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = RegistryManager::new(dir.path().to_path_buf(), None, SyncPolicy::Offline, 4);
+        manager.api_base = server.base.clone();
+
+        let release = manager.get_release_by_tag("o/r", "cli/v1.0+build").await.unwrap().unwrap();
+        assert_eq!(release["tag_name"], "cli/v1.0+build");
+    }
+
+    fn local_registry(root: &Path, name: &str, priority: i32, packages: &[(&str, &str)]) -> Registry {
+        let path = root.join(name);
+        std::fs::create_dir_all(path.join("packages")).unwrap();
+        for (package, repository) in packages {
+            let definition = serde_json::json!({ "name": package, "repository": repository });
+            std::fs::write(path.join("packages").join(format!("{}.json", package)), definition.to_string()).unwrap();
+        }
+        registry(name, &path.to_string_lossy(), priority)
+    }
+
+    #[tokio::test]
+    async fn find_package_in_local_registries_by_priority() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().join("data"), None, SyncPolicy::Manual, 1);
+        let registries = [
+            local_registry(dir.path(), "community", 0, &[("ripgrep", "BurntSushi/ripgrep"), ("fd", "sharkdp/fd")]),
+            local_registry(dir.path(), "internal", 10, &[("ripgrep", "internal/ripgrep-fork")]),
+        ];
+
+        let package = manager.find_package(&registries, "ripgrep").await.unwrap();
+        assert_eq!(package.registry, "internal");
+        assert_eq!(package.info.repository, "internal/ripgrep-fork");
+
+        let package = manager.find_package(&registries, "fd").await.unwrap();
+        assert_eq!(package.registry, "community");
+
+        let missing = manager.find_package(&registries, "bat").await.err().unwrap();
+        assert!(matches!(missing.downcast_ref(), Some(GripError::PackageNotFound(name)) if name == "bat"));
+
+        // Read in place: nothing is copied into the data directory
+        let copies = std::fs::read_dir(dir.path().join("data/registries")).map_or(0, |entries| entries.count());
+        assert_eq!(copies, 0);
+        let names: Vec<_> = manager
+            .list_packages(&registries)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| format!("{}/{}", p.registry, p.info.name))
+            .collect();
+        assert_eq!(names, ["internal/ripgrep", "community/fd", "community/ripgrep"]);
+    }
+
+    #[tokio::test]
+    async fn local_registry_without_packages_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = RegistryManager::new(dir.path().join("data"), None, SyncPolicy::Manual, 1);
+        let mut broken = local_registry(dir.path(), "broken", 10, &[]);
+        broken.source = RegistrySource::Local(dir.path().join("missing"));
+        let registries = [broken, local_registry(dir.path(), "community", 0, &[("fd", "sharkdp/fd")])];
+
+        let package = manager.find_package(&registries, "fd").await.unwrap();
+        assert_eq!(package.registry, "community");
+
+        assert!(manager.find_package(&registries[..1], "fd").await.is_err());
+    }
+}
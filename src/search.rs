@@ -0,0 +1,150 @@
+use crate::package::{Package, PackageInfo};
+
+/// Packages matching `query` and carrying every tag in `tags`, best match first and by name among equals
+pub fn rank<'a>(packages: &'a [Package], query: &str, tags: &[String]) -> Vec<(u32, &'a Package)> {
+    let mut hits: Vec<(u32, &Package)> = packages
+        .iter()
+        .filter(|package| has_tags(&package.info, tags))
+        .filter_map(|package| Some((score(query, &package.info)?, package)))
+        .collect();
+    hits.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then(a.info.name.cmp(&b.info.name)));
+    hits
+}
+
+/// Rank a package against a search query. Every word of the query has to match the name, a tag or
+/// the description; exact and prefix name matches rank highest, then tags, near-miss spellings of
+/// the name, the description and finally the query's letters appearing in order in the name.
+pub fn score(query: &str, info: &PackageInfo) -> Option<u32> {
+    let name = info.name.to_lowercase();
+    let tags: Vec<String> = info.tags.iter().map(|t| t.to_lowercase()).collect();
+    let description = info.description.as_deref().unwrap_or("").to_lowercase();
+
+    let mut total = 0;
+    for term in query.split_whitespace().map(str::to_lowercase) {
+        let term_score = if name == term {
+            100
+        } else if name.starts_with(&term) {
+            80
+        } else if name.contains(&term) {
+            60
+        } else if tags.contains(&term) {
+            50
+        } else if edit_distance(&name, &term) <= (term.len() / 4).max(1) {
+            40
+        } else if tags.iter().any(|tag| tag.contains(&term)) {
+            30
+        } else if description.contains(&term) {
+            20
+        } else if is_subsequence(&term, &name) {
+            10
+        } else {
+            return None;
+        };
+        total += term_score;
+    }
+
+    Some(total)
+}
+
+/// Whether the package carries every tag in `tags`, ignoring case
+pub fn has_tags(info: &PackageInfo, tags: &[String]) -> bool {
+    tags.iter()
+        .all(|wanted| info.tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted)))
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle.chars().all(|c| chars.any(|h| h == c))
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, description: &str, tags: &[&str]) -> Package {
+        let info = serde_json::json!({
+            "name": name,
+            "repository": format!("owner/{}", name),
+            "description": description,
+            "tags": tags,
+        });
+        Package {
+            info: serde_json::from_value(info).unwrap(),
+            registry: "default".to_string(),
+        }
+    }
+
+    fn names<'a>(hits: &[(u32, &'a Package)]) -> Vec<&'a str> {
+        hits.iter().map(|(_, p)| p.info.name.as_str()).collect()
+    }
+
+    #[test]
+    fn name_matches_outrank_tags_and_descriptions() {
+        let packages = [
+            package("ripgrep", "Recursively search directories", &["search", "grep"]),
+            package("ugrep", "Ultra fast grep with interactive query UI", &["search"]),
+            package("grep-ast", "Grep source code by syntax tree", &[]),
+            package("fd", "A simple alternative to find", &["search"]),
+            package("bat", "A cat clone with wings", &[]),
+        ];
+
+        let hits = rank(&packages, "grep", &[]);
+        assert_eq!(names(&hits), ["grep-ast", "ripgrep", "ugrep"]);
+        assert!(hits[0].0 > hits[1].0);
+
+        // A tag beats a description mention
+        let hits = rank(&packages, "search", &[]);
+        assert_eq!(names(&hits), ["fd", "ripgrep", "ugrep"]);
+        assert_eq!(names(&rank(&packages, "wings", &[])), ["bat"]);
+    }
+
+    #[test]
+    fn equal_scores_are_ordered_by_name() {
+        let packages = [
+            package("zoxide", "", &["shell"]),
+            package("atuin", "", &["shell"]),
+            package("mcfly", "", &["shell"]),
+        ];
+        let hits = rank(&packages, "shell", &[]);
+        assert_eq!(names(&hits), ["atuin", "mcfly", "zoxide"]);
+        assert!(hits.iter().all(|(score, _)| *score == hits[0].0));
+    }
+
+    #[test]
+    fn every_word_must_match_and_tags_filter() {
+        let packages = [
+            package("ruff", "An extremely fast Python linter", &["lint", "python"]),
+            package("shellcheck", "Static analysis for shell scripts", &["lint", "shell"]),
+        ];
+
+        assert_eq!(names(&rank(&packages, "fast linter", &[])), ["ruff"]);
+        assert!(rank(&packages, "fast javascript", &[]).is_empty());
+        assert_eq!(names(&rank(&packages, "", &["LINT".to_string()])), ["ruff", "shellcheck"]);
+        assert_eq!(names(&rank(&packages, "", &["lint".to_string(), "shell".to_string()])), ["shellcheck"]);
+    }
+
+    #[test]
+    fn near_misses_and_abbreviations_still_match() {
+        let packages = [package("ripgrep", "", &[]), package("kubectl", "", &[])];
+        assert_eq!(names(&rank(&packages, "ripgrap", &[])), ["ripgrep"]);
+        assert_eq!(names(&rank(&packages, "kctl", &[])), ["kubectl"]);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::error::{GripError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+/// How strictly release signatures are enforced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturePolicy {
+    /// Never look for signatures
+    Off,
+    /// Verify signatures when keys and signatures are available, warn otherwise
+    #[default]
+    Warn,
+    /// Refuse to install anything without a valid signature
+    Require,
+}
+
+/// Keyless cosign verification against a Fulcio certificate identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CosignIdentity {
+    pub identity: String,
+    pub issuer: String,
+}
+
+/// Public keys declared by a registry for verifying a package's releases
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SigningKeys {
+    /// Minisign public key, either the base64 key line or the whole `minisign.pub` file
+    pub minisign: Option<String>,
+    /// ASCII-armored OpenPGP public key
+    pub gpg: Option<String>,
+    /// PEM encoded cosign public key
+    pub cosign: Option<String>,
+    /// Certificate identity for keyless cosign signatures
+    pub cosign_identity: Option<CosignIdentity>,
+}
+
+impl SigningKeys {
+    pub fn is_empty(&self) -> bool {
+        self.minisign.is_none() && self.gpg.is_none() && self.cosign.is_none() && self.cosign_identity.is_none()
+    }
+
+    fn has_cosign(&self) -> bool {
+        self.cosign.is_some() || self.cosign_identity.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureKind {
+    Minisign,
+    Gpg,
+    CosignSignature,
+    CosignBundle,
+}
+
+impl std::fmt::Display for SignatureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureKind::Minisign => write!(f, "minisign"),
+            SignatureKind::Gpg => write!(f, "GPG"),
+            SignatureKind::CosignSignature | SignatureKind::CosignBundle => write!(f, "cosign"),
+        }
+    }
+}
+
+/// Result of checking a signature that did not fail outright
+pub enum Verification {
+    Verified,
+    /// The external tool needed for this kind of signature isn't installed
+    ToolMissing(&'static str),
+}
+
+/// A release asset holding a signature over another asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureAsset {
+    pub index: usize,
+    pub kind: SignatureKind,
+    /// The certificate a keyless cosign signature is checked against
+    pub certificate: Option<usize>,
+}
+
+/// Release assets holding a signature for `target` that one of `keys` can check
+pub fn signature_assets(target: &str, names: &[&str], keys: &SigningKeys) -> Vec<SignatureAsset> {
+    let mut candidates = Vec::new();
+
+    for (index, name) in names.iter().enumerate() {
+        let Some(suffix) = name.strip_prefix(target) else {
+            continue;
+        };
+
+        let kinds: &[SignatureKind] = match suffix {
+            ".minisig" => &[SignatureKind::Minisign],
+            ".asc" | ".gpg" => &[SignatureKind::Gpg],
+            // `.sig` is used by both GPG and cosign, try whichever keys we have
+            ".sig" => &[SignatureKind::Gpg, SignatureKind::CosignSignature],
+            ".bundle" | ".sigstore" | ".sigstore.json" => &[SignatureKind::CosignBundle],
+            _ => &[],
+        };
+
+        for kind in kinds {
+            let usable = match kind {
+                SignatureKind::Minisign => keys.minisign.is_some(),
+                SignatureKind::Gpg => keys.gpg.is_some(),
+                SignatureKind::CosignSignature | SignatureKind::CosignBundle => keys.has_cosign(),
+            };
+            if !usable {
+                continue;
+            }
+
+            // Without a public key a plain cosign signature is only as good as the Fulcio
+            // certificate published next to it, e.g. `tool.tar.gz.pem`
+            let certificate = if *kind == SignatureKind::CosignSignature && keys.cosign.is_none() {
+                let certificate = [".pem", ".crt", ".cert"]
+                    .iter()
+                    .find_map(|ext| names.iter().position(|n| *n == format!("{}{}", target, ext)));
+                if certificate.is_none() {
+                    continue;
+                }
+                certificate
+            } else {
+                None
+            };
+
+            candidates.push(SignatureAsset {
+                index,
+                kind: *kind,
+                certificate,
+            });
+        }
+    }
+
+    candidates
+}
+
+fn invalid(msg: String) -> anyhow::Error {
+    GripError::SignatureError(msg).into()
+}
+
+/// Check `signature` over the file at `file` with the matching key from `keys`, and the signing
+/// certificate for keyless cosign signatures
+pub async fn verify(
+    kind: SignatureKind,
+    keys: &SigningKeys,
+    file: &Path,
+    signature: &[u8],
+    certificate: Option<&[u8]>,
+) -> Result<Verification> {
+    match kind {
+        SignatureKind::Minisign => verify_minisign(keys, file, signature).map(|_| Verification::Verified),
+        SignatureKind::Gpg => verify_gpg(keys, file, signature).await,
+        SignatureKind::CosignSignature | SignatureKind::CosignBundle => {
+            verify_cosign(kind, keys, file, signature, certificate).await
+        }
+    }
+}
+
+fn verify_minisign(keys: &SigningKeys, file: &Path, signature: &[u8]) -> Result<()> {
+    let key = keys.minisign.as_deref().unwrap_or_default().trim();
+    let public_key = if key.contains('\n') {
+        minisign_verify::PublicKey::decode(key)
+    } else {
+        minisign_verify::PublicKey::from_base64(key)
+    }
+    .map_err(|e| invalid(format!("invalid minisign public key in registry: {}", e)))?;
+
+    let signature = minisign_verify::Signature::decode(&String::from_utf8_lossy(signature))
+        .map_err(|e| invalid(format!("malformed minisign signature: {}", e)))?;
+
+    let data = std::fs::read(file)?;
+    public_key
+        .verify(&data, &signature, true)
+        .map_err(|e| invalid(format!("minisign verification failed: {}", e)))
+}
+
+/// Run an external verifier, returning `None` if it isn't installed
+async fn run_tool(command: &mut Command) -> Result<Option<std::process::Output>> {
+    match command.output().await {
+        Ok(output) => Ok(Some(output)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn verify_gpg(keys: &SigningKeys, file: &Path, signature: &[u8]) -> Result<Verification> {
+    let key = keys.gpg.as_deref().unwrap_or_default();
+
+    // Use a throwaway keyring so only the key declared by the registry is trusted
+    let home = tempfile::tempdir()?;
+    let key_path = home.path().join("key.asc");
+    let signature_path = home.path().join("signature");
+    std::fs::write(&key_path, key)?;
+    std::fs::write(&signature_path, signature)?;
+
+    let Some(import) = run_tool(
+        Command::new("gpg")
+            .arg("--batch")
+            .arg("--quiet")
+            .arg("--homedir")
+            .arg(home.path())
+            .arg("--import")
+            .arg(&key_path),
+    )
+    .await?
+    else {
+        return Ok(Verification::ToolMissing("gpg"));
+    };
+
+    if !import.status.success() {
+        return Err(invalid(format!(
+            "failed to import GPG key from registry: {}",
+            String::from_utf8_lossy(&import.stderr).trim()
+        )));
+    }
+
+    let output = Command::new("gpg")
+        .arg("--batch")
+        .arg("--quiet")
+        .arg("--homedir")
+        .arg(home.path())
+        .arg("--verify")
+        .arg(&signature_path)
+        .arg(file)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(invalid(format!(
+            "GPG verification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(Verification::Verified)
+}
+
+async fn verify_cosign(
+    kind: SignatureKind,
+    keys: &SigningKeys,
+    file: &Path,
+    signature: &[u8],
+    certificate: Option<&[u8]>,
+) -> Result<Verification> {
+    let work = tempfile::tempdir()?;
+    let signature_path = work.path().join("signature");
+    std::fs::write(&signature_path, signature)?;
+
+    let mut command = Command::new("cosign");
+    command.arg("verify-blob");
+
+    if let Some(key) = &keys.cosign {
+        let key_path = work.path().join("cosign.pub");
+        std::fs::write(&key_path, key)?;
+        command.arg("--key").arg(key_path);
+    } else if let Some(identity) = &keys.cosign_identity {
+        command
+            .arg("--certificate-identity")
+            .arg(&identity.identity)
+            .arg("--certificate-oidc-issuer")
+            .arg(&identity.issuer);
+
+        // Bundles carry their certificate, detached signatures need the published one
+        if let Some(certificate) = certificate {
+            let certificate_path = work.path().join("certificate.pem");
+            std::fs::write(&certificate_path, certificate)?;
+            command.arg("--certificate").arg(certificate_path);
+        }
+    }
+
+    match kind {
+        SignatureKind::CosignBundle => command.arg("--bundle").arg(&signature_path),
+        _ => command.arg("--signature").arg(&signature_path),
+    };
+    command.arg(file);
+
+    let Some(output) = run_tool(&mut command).await? else {
+        return Ok(Verification::ToolMissing("cosign"));
+    };
+
+    if !output.status.success() {
+        return Err(invalid(format!(
+            "cosign verification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(Verification::Verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyless() -> SigningKeys {
+        SigningKeys {
+            cosign_identity: Some(CosignIdentity {
+                identity: "https://github.com/example/tool/.github/workflows/release.yml@refs/heads/main".to_string(),
+                issuer: "https://token.actions.githubusercontent.com".to_string(),
+            }),
+            ..SigningKeys::default()
+        }
+    }
+
+    #[test]
+    fn keyless_signatures_are_paired_with_their_certificate() {
+        let names = ["tool.tar.gz", "tool.tar.gz.sig", "tool.tar.gz.pem"];
+        assert_eq!(
+            signature_assets("tool.tar.gz", &names, &keyless()),
+            vec![SignatureAsset { index: 1, kind: SignatureKind::CosignSignature, certificate: Some(2) }]
+        );
+    }
+
+    #[test]
+    fn keyless_signatures_without_certificate_are_skipped() {
+        let names = ["tool.tar.gz", "tool.tar.gz.sig"];
+        assert!(signature_assets("tool.tar.gz", &names, &keyless()).is_empty());
+    }
+
+    #[test]
+    fn signatures_only_for_declared_keys() {
+        let keys = SigningKeys {
+            minisign: Some("key".to_string()),
+            cosign: Some("-----BEGIN PUBLIC KEY-----".to_string()),
+            ..SigningKeys::default()
+        };
+        let names = ["tool.zip", "tool.zip.minisig", "tool.zip.sig", "tool.zip.asc", "tool.zip.bundle", "other.zip.minisig"];
+
+        let found: Vec<(usize, SignatureKind)> = signature_assets("tool.zip", &names, &keys)
+            .into_iter()
+            .map(|sig| (sig.index, sig.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, SignatureKind::Minisign),
+                (2, SignatureKind::CosignSignature),
+                (4, SignatureKind::CosignBundle),
+            ]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::archive::ArchiveKind;
+use crate::error::GripError;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// Where a registry lives, parsed from the `url` of a registry entry:
+/// - a git repository: `github.com/owner/repo`, `https://host/group/sub/repo.git`,
+///   `ssh://git@host/repo` or `git@host:group/repo`, optionally followed by `//subdir` and
+///   either `#branch` or `@rev`
+/// - an HTTP index: an `http(s)://` URL ending in `.json` or an archive extension
+/// - a local directory: `file://...`, an absolute path, `./...`, `../...` or `~/...`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RegistrySource {
+    Git(GitSource),
+    Http(String),
+    Local(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+    /// The repository as written, without subdirectory and ref
+    pub repository: String,
+    pub reference: Option<GitRef>,
+    /// Directory inside the repository that holds `packages/`
+    pub subdir: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitRef {
+    /// `#branch`: follow a branch
+    Branch(String),
+    /// `@rev`: stay on a tag or commit
+    Rev(String),
+}
+
+impl GitSource {
+    pub fn new(repository: &str) -> Self {
+        Self {
+            repository: repository.to_string(),
+            reference: None,
+            subdir: None,
+        }
+    }
+
+    /// The URL handed to git; `host/path` shorthands are cloned over HTTPS
+    pub fn clone_url(&self) -> String {
+        if self.repository.contains("://") || is_scp_like(&self.repository) {
+            self.repository.clone()
+        } else if self.repository.ends_with(".git") {
+            format!("https://{}", self.repository)
+        } else {
+            format!("https://{}.git", self.repository)
+        }
+    }
+}
+
+/// `[user@]host:path`, git's scp-like syntax: a colon before the first slash
+fn is_scp_like(url: &str) -> bool {
+    match (url.find(':'), url.find('/')) {
+        (Some(colon), Some(slash)) => colon < slash,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+fn invalid(url: &str, reason: &str) -> GripError {
+    GripError::RegistryError(format!("Invalid registry URL '{}': {}", url, reason))
+}
+
+/// Check the host and path parts of a repository URL without a ref or subdirectory
+fn validate_repository(url: &str, repository: &str) -> Result<(), GripError> {
+    let (host, path) = if let Some((scheme, rest)) = repository.split_once("://") {
+        if !matches!(scheme, "https" | "http" | "ssh" | "git") {
+            return Err(invalid(url, &format!("unsupported scheme '{}'", scheme)));
+        }
+        rest.split_once('/').unwrap_or((rest, ""))
+    } else if is_scp_like(repository) {
+        repository.split_once(':').unwrap_or((repository, ""))
+    } else {
+        repository.split_once('/').unwrap_or((repository, ""))
+    };
+
+    let host = host.rsplit('@').next().unwrap_or_default();
+    if host.is_empty() {
+        return Err(invalid(url, "missing host"));
+    }
+    if path.trim_matches('/').is_empty() {
+        return Err(invalid(url, "missing repository path"));
+    }
+
+    Ok(())
+}
+
+impl FromStr for RegistrySource {
+    type Err = GripError;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        if url.is_empty() {
+            return Err(invalid(url, "empty"));
+        }
+        if url.chars().any(char::is_whitespace) {
+            return Err(invalid(url, "contains whitespace"));
+        }
+
+        if let Some(path) = url.strip_prefix("file://") {
+            if path.is_empty() {
+                return Err(invalid(url, "missing path"));
+            }
+            return Ok(RegistrySource::Local(PathBuf::from(path)));
+        }
+        if let Some(path) = url.strip_prefix("~/") {
+            let home = dirs::home_dir().ok_or_else(|| invalid(url, "no home directory"))?;
+            return Ok(RegistrySource::Local(home.join(path)));
+        }
+        if url.starts_with('.') || Path::new(url).is_absolute() {
+            return Ok(RegistrySource::Local(PathBuf::from(url)));
+        }
+
+        let lower = url.to_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            let path = lower.split(['?', '#']).next().unwrap_or_default();
+            if path.ends_with(".json") || ArchiveKind::detect(path).is_some() {
+                return Ok(RegistrySource::Http(url.to_string()));
+            }
+        }
+
+        // A ref comes last: `#branch`, or `@rev` in the final path segment so `git@host:` is left alone
+        let (rest, reference) = if let Some((rest, branch)) = url.rsplit_once('#') {
+            (rest, Some(GitRef::Branch(branch.to_string())))
+        } else {
+            let segment_start = url.rfind(['/', ':']).map_or(0, |i| i + 1);
+            match url[segment_start..].rfind('@') {
+                Some(at) => (
+                    &url[..segment_start + at],
+                    Some(GitRef::Rev(url[segment_start + at + 1..].to_string())),
+                ),
+                None => (url, None),
+            }
+        };
+        if let Some(GitRef::Branch(name) | GitRef::Rev(name)) = &reference {
+            if name.is_empty() {
+                return Err(invalid(url, "empty branch or revision"));
+            }
+        }
+
+        // `//` separates the repository from a subdirectory, after any `scheme://`
+        let path_start = rest.find("://").map_or(0, |i| i + 3);
+        let (repository, subdir) = match rest[path_start..].find("//") {
+            Some(i) => (
+                &rest[..path_start + i],
+                Some(rest[path_start + i + 2..].trim_matches('/').to_string()),
+            ),
+            None => (rest, None),
+        };
+        if let Some(subdir) = &subdir {
+            let escapes = Path::new(subdir)
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_)));
+            if subdir.is_empty() || escapes {
+                return Err(invalid(url, "subdirectory must be a relative path inside the repository"));
+            }
+        }
+
+        validate_repository(url, repository)?;
+
+        Ok(RegistrySource::Git(GitSource {
+            repository: repository.to_string(),
+            reference,
+            subdir,
+        }))
+    }
+}
+
+impl std::fmt::Display for RegistrySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistrySource::Git(git) => {
+                write!(f, "{}", git.repository)?;
+                if let Some(subdir) = &git.subdir {
+                    write!(f, "//{}", subdir)?;
+                }
+                match &git.reference {
+                    Some(GitRef::Branch(branch)) => write!(f, "#{}", branch),
+                    Some(GitRef::Rev(rev)) => write!(f, "@{}", rev),
+                    None => Ok(()),
+                }
+            }
+            RegistrySource::Http(url) => write!(f, "{}", url),
+            RegistrySource::Local(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl TryFrom<String> for RegistrySource {
+    type Error = GripError;
+
+    fn try_from(url: String) -> Result<Self, Self::Error> {
+        url.parse()
+    }
+}
+
+impl From<RegistrySource> for String {
+    fn from(source: RegistrySource) -> Self {
+        source.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(url: &str) -> GitSource {
+        match url.parse::<RegistrySource>().unwrap() {
+            RegistrySource::Git(git) => git,
+            other => panic!("{} parsed as {:?}", url, other),
+        }
+    }
+
+    #[test]
+    fn shorthand_is_cloned_over_https() {
+        let source = git("github.com/Grip-Packages/Grip-Packages");
+        assert_eq!(source, GitSource::new("github.com/Grip-Packages/Grip-Packages"));
+        assert_eq!(source.clone_url(), "https://github.com/Grip-Packages/Grip-Packages.git");
+        assert_eq!(git("gitlab.com/group/registry.git").clone_url(), "https://gitlab.com/group/registry.git");
+    }
+
+    #[test]
+    fn full_and_ssh_urls_are_kept() {
+        for url in [
+            "https://gitlab.example.com/group/sub/registry.git",
+            "ssh://git@gitlab.example.com:2222/group/registry.git",
+            "git@gitlab.example.com:group/sub/registry.git",
+        ] {
+            assert_eq!(git(url).clone_url(), url);
+        }
+    }
+
+    #[test]
+    fn refs_and_subdirectories() {
+        let source = git("git@gitlab.example.com:platform/tools.git//registry#stable");
+        assert_eq!(source.repository, "git@gitlab.example.com:platform/tools.git");
+        assert_eq!(source.subdir.as_deref(), Some("registry"));
+        assert_eq!(source.reference, Some(GitRef::Branch("stable".to_string())));
+
+        let source = git("https://example.com/tools.git//grip/registry@v2.1.0");
+        assert_eq!(source.repository, "https://example.com/tools.git");
+        assert_eq!(source.subdir.as_deref(), Some("grip/registry"));
+        assert_eq!(source.reference, Some(GitRef::Rev("v2.1.0".to_string())));
+
+        // The user in an SSH URL is not a revision
+        assert_eq!(git("git@host:registry").reference, None);
+        assert_eq!(git("ssh://git@host/registry").reference, None);
+    }
+
+    #[test]
+    fn http_indexes_and_local_paths() {
+        for url in ["https://example.com/grip/index.json", "https://example.com/registry.tar.gz?token=1"] {
+            assert_eq!(url.parse::<RegistrySource>().unwrap(), RegistrySource::Http(url.to_string()));
+        }
+
+        assert_eq!(
+            "file:///srv/registry".parse::<RegistrySource>().unwrap(),
+            RegistrySource::Local(PathBuf::from("/srv/registry"))
+        );
+        assert_eq!(
+            "./vendor/registry".parse::<RegistrySource>().unwrap(),
+            RegistrySource::Local(PathBuf::from("./vendor/registry"))
+        );
+    }
+
+    #[test]
+    fn invalid_urls_are_rejected() {
+        for url in [
+            "",
+            "github.com",
+            "git@host",
+            "ftp://example.com/registry",
+            "https://example.com",
+            "github.com/o/r#",
+            "github.com/o/r@",
+            "github.com/o/r//../escape",
+            "github.com/o/r//",
+            "github.com/o/my registry",
+        ] {
+            assert!(url.parse::<RegistrySource>().is_err(), "{} was accepted", url);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for url in [
+            "github.com/o/r",
+            "git@gitlab.example.com:group/registry.git//sub#main",
+            "ssh://git@host:2222/registry@0123abcd",
+            "https://example.com/index.json",
+        ] {
+            let source: RegistrySource = url.parse().unwrap();
+            assert_eq!(source.to_string(), url);
+            assert_eq!(source.to_string().parse::<RegistrySource>().unwrap(), source);
+        }
+    }
+
+    #[test]
+    fn config_entries_are_parsed_on_load() {
+        let registry: crate::config::Registry =
+            serde_json::from_str(r#"{ "name": "internal", "url": "git@host:tools/registry.git#main", "priority": 10 }"#)
+                .unwrap();
+        assert_eq!(registry.source, "git@host:tools/registry.git#main".parse().unwrap());
+
+        let invalid = serde_json::from_str::<crate::config::Registry>(r#"{ "name": "x", "url": "github.com", "priority": 0 }"#);
+        assert!(invalid.is_err());
+    }
+}
@@ -1,21 +1,16 @@
 use crate::error::Result;
-use std::path::Path;
-
-pub async fn extract_archive(archive_path: &Path, target_dir: &Path) -> Result<()> {
-    if archive_path.extension().map_or(false, |ext| ext == "zip") {
-        let file = std::fs::File::open(archive_path)?;
-        let mut archive = zip::ZipArchive::new(file)?;
-        archive.extract(target_dir)?;
-    } else if archive_path.extension().map_or(false, |ext| ext == "gz" || ext == "tgz") {
-        use std::process::Command;
-        
-        Command::new("tar")
-            .args(&["xzf", &archive_path.to_string_lossy()])
-            .current_dir(target_dir)
-            .status()?;
-    }
-    
-    Ok(())
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STATUS_ON_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send progress messages to stderr from now on, leaving stdout to a tool run by `grip exec`
+pub fn send_status_to_stderr() {
+    STATUS_ON_STDERR.store(true, Ordering::Relaxed);
+}
+
+pub fn status_on_stderr() -> bool {
+    STATUS_ON_STDERR.load(Ordering::Relaxed)
 }
 
 pub fn get_platform() -> &'static str {
@@ -43,30 +38,39 @@ pub fn get_arch() -> &'static str {
     return "unknown";
 }
 
-pub fn get_binary_extension() -> &'static str {
+/// Returns whether the given path points to a binary file
+pub fn is_binary(path: &Path) -> bool {
     #[cfg(target_os = "windows")]
-    return "exe";
-    
+    return path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+
     #[cfg(not(target_os = "windows"))]
-    return "";
+    {
+        // On Unix systems, check if the file is executable
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
 }
 
-/// Returns whether the given path points to a binary file
-pub fn is_binary(path: &Path) -> bool {
-    if let Some(extension) = path.extension() {
-        #[cfg(target_os = "windows")]
-        return extension == "exe";
-        
-        #[cfg(not(target_os = "windows"))]
-        {
-            // On Unix systems, check if the file is executable
-            use std::os::unix::fs::PermissionsExt;
-            if let Ok(metadata) = path.metadata() {
-                return metadata.permissions().mode() & 0o111 != 0;
-            }
+/// Recursively collect the binaries inside a directory
+pub fn find_binaries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut binaries = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_symlink() {
+            continue;
+        }
+        if path.is_dir() {
+            binaries.extend(find_binaries(&path)?);
+        } else if is_binary(&path) {
+            binaries.push(path);
         }
     }
-    false
+
+    binaries.sort();
+    Ok(binaries)
 }
 
 /// Make a file executable
@@ -81,6 +85,15 @@ pub fn make_executable(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Whether `name` can be joined onto a directory without leaving it, i.e. it is a
+/// single plain path component such as `ripgrep` or `v14.1.0` and not `..` or `a/b`
+pub fn is_plain_component(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(std::path::Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\'])
+}
+
 /// Expand environment variables in a path string
 pub fn expand_path(path: &str) -> String {
     let mut result = path.to_string();
@@ -116,4 +129,54 @@ pub fn create_symlink(src: &Path, dst: &Path) -> Result<()> {
         std::os::windows::fs::symlink_dir(src, dst)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Run a binary with inherited stdio and hand its exit code back.
+/// On Unix the current process is replaced, so signals reach the tool directly.
+#[cfg(unix)]
+pub fn run_binary(binary: &Path, args: &[String]) -> Result<i32> {
+    use std::os::unix::process::CommandExt;
+
+    let err = std::process::Command::new(binary).args(args).exec();
+    Err(anyhow::anyhow!("Failed to run {}: {}", binary.display(), err))
+}
+
+#[cfg(windows)]
+pub fn run_binary(binary: &Path, args: &[String]) -> Result<i32> {
+    // Ctrl+C is delivered to every process on the console, the child handles it itself
+    let status = std::process::Command::new(binary)
+        .args(args)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", binary.display(), e))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_components() {
+        for name in ["ripgrep", "v14.1.0", "cli@1.2.0", "..a", "a..b"] {
+            assert!(is_plain_component(name), "{}", name);
+        }
+        for name in ["", ".", "..", "../..", "a/b", "a\\b", "/etc", "./a"] {
+            assert!(!is_plain_component(name), "{}", name);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_executables_in_nested_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("tool-1.0-linux/bin");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(nested.join("tool"), "#!/bin/sh\n").unwrap();
+        std::fs::write(dir.path().join("README.md"), "docs").unwrap();
+        make_executable(&nested.join("tool")).unwrap();
+        std::os::unix::fs::symlink(nested.join("tool"), dir.path().join("tool-link")).unwrap();
+
+        assert_eq!(find_binaries(dir.path()).unwrap(), [nested.join("tool")]);
+        assert!(!is_binary(&nested));
+    }
+}
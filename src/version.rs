@@ -0,0 +1,221 @@
+use semver::{Version, VersionReq};
+use std::cmp::Ordering;
+
+/// What was asked for with `--version`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Requirement {
+    Latest,
+    /// A full version such as `1.2.3`, matched against tags whatever their prefix
+    Exact(Version),
+    /// A range such as `^14`, `~0.22` or `>=1.5,<2`
+    Range(VersionReq),
+    /// Anything else is taken as a literal tag name
+    Tag(String),
+}
+
+impl Requirement {
+    pub fn parse(input: &str) -> Self {
+        let input = input.trim();
+        if input.eq_ignore_ascii_case("latest") {
+            return Requirement::Latest;
+        }
+
+        let is_range = input.starts_with(['^', '~', '=', '<', '>', '*']) || input.contains(',');
+        if !is_range {
+            if let Some((version, 3)) = parse_version(strip_prefix(input)) {
+                return Requirement::Exact(version);
+            }
+        }
+
+        // Partial versions such as `14` or `v1.5` match that prefix, i.e. `14.x` or `1.5.x`
+        let range = if is_range {
+            input.to_string()
+        } else {
+            format!("~{}", strip_prefix(input))
+        };
+        match VersionReq::parse(&range) {
+            Ok(req) => Requirement::Range(req),
+            Err(_) => Requirement::Tag(input.to_string()),
+        }
+    }
+
+    pub fn matches(&self, tag: &str) -> bool {
+        match self {
+            Requirement::Latest => true,
+            Requirement::Exact(version) => parse_tag(tag).as_ref() == Some(version),
+            Requirement::Range(req) => parse_tag(tag).is_some_and(|v| req.matches(&v)),
+            Requirement::Tag(name) => tag == name,
+        }
+    }
+}
+
+/// Parse a release tag into a version. Understands `v1.2.3`, `release-1.2.3`, monorepo tags such as
+/// `tool@1.2.3` and `tool-v1.2.3`, and pads short versions like `v14` to `14.0.0`.
+pub fn parse_tag(tag: &str) -> Option<Version> {
+    parse_version(strip_prefix(tag)).map(|(version, _)| version)
+}
+
+/// Drop everything before the version number: the part up to the last `@` or `/`, then a prefix
+/// such as `v`, `release-` or `tool-v`. The version starts at the beginning or right after a `-` or
+/// `_`, so digits in a tool's name (`h2-v1.2.3`, `s5cmd-v2.0.0`) are kept out of it; the earliest
+/// such point wins so `tool-1.2.3-rc.1` keeps its prerelease.
+fn strip_prefix(tag: &str) -> &str {
+    let tag = tag.rsplit(['@', '/']).next().unwrap_or(tag);
+
+    let starts = std::iter::once(0).chain(tag.match_indices(['-', '_']).map(|(i, _)| i + 1));
+    for start in starts {
+        let rest = &tag[start..];
+        let rest = rest.strip_prefix(['v', 'V']).unwrap_or(rest);
+        if parse_version(rest).is_some() {
+            return rest;
+        }
+    }
+
+    // Tags like `go1.21.0` have no separator at all, cut at the first digit
+    let start = tag.find(|c: char| c.is_ascii_digit()).unwrap_or(tag.len());
+    &tag[start..]
+}
+
+/// Parse a version that may have fewer than three components, returning how many it had
+fn parse_version(s: &str) -> Option<(Version, usize)> {
+    let core_end = s.find(['-', '+']).unwrap_or(s.len());
+    let (core, rest) = s.split_at(core_end);
+
+    let parts = core.split('.').count();
+    let padded = match parts {
+        1 => format!("{}.0.0{}", core, rest),
+        2 => format!("{}.0{}", core, rest),
+        3 => s.to_string(),
+        _ => return None,
+    };
+
+    Version::parse(&padded).ok().map(|version| (version, parts))
+}
+
+/// Keep only the releases of one tool in a repository that releases several, by tag prefix
+pub fn retain_tool(releases: &mut Vec<serde_json::Value>, tag_prefix: Option<&str>) {
+    if let Some(prefix) = tag_prefix {
+        releases.retain(|r| is_tool_tag(r["tag_name"].as_str().unwrap_or(""), Some(prefix)));
+    }
+}
+
+/// Whether a tag belongs to the tool with the given prefix; every tag does when there is none
+pub fn is_tool_tag(tag: &str, tag_prefix: Option<&str>) -> bool {
+    tag_prefix.is_none_or(|prefix| tag.starts_with(prefix))
+}
+
+/// Sort releases newest version first. Tags that aren't versions go last, in their original order.
+pub fn sort_releases(releases: &mut [serde_json::Value]) {
+    releases.sort_by(|a, b| {
+        let a = a["tag_name"].as_str().and_then(parse_tag);
+        let b = b["tag_name"].as_str().and_then(parse_tag);
+        match (a, b) {
+            (Some(a), Some(b)) => b.cmp(&a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parse_tag_strips_prefixes() {
+        assert_eq!(parse_tag("v1.2.3"), Some(version("1.2.3")));
+        assert_eq!(parse_tag("1.2.3"), Some(version("1.2.3")));
+        assert_eq!(parse_tag("release-1.2.3"), Some(version("1.2.3")));
+        assert_eq!(parse_tag("tool@1.2.3"), Some(version("1.2.3")));
+        assert_eq!(parse_tag("cli/v0.4.0"), Some(version("0.4.0")));
+        assert_eq!(parse_tag("tool-v1.2.3"), Some(version("1.2.3")));
+        assert_eq!(parse_tag("go1.21.0"), Some(version("1.21.0")));
+    }
+
+    #[test]
+    fn parse_tag_keeps_digits_in_tool_names_out_of_the_version() {
+        assert_eq!(parse_tag("h2-v1.2.3"), Some(version("1.2.3")));
+        assert_eq!(parse_tag("s5cmd-v2.0.0"), Some(version("2.0.0")));
+        assert_eq!(parse_tag("k9s_v0.32.4"), Some(version("0.32.4")));
+    }
+
+    #[test]
+    fn parse_tag_pads_short_versions_and_keeps_prereleases() {
+        assert_eq!(parse_tag("v14"), Some(version("14.0.0")));
+        assert_eq!(parse_tag("v1.5"), Some(version("1.5.0")));
+        assert_eq!(parse_tag("tool-1.2.3-rc.1"), Some(version("1.2.3-rc.1")));
+        assert_eq!(parse_tag("nightly"), None);
+    }
+
+    #[test]
+    fn requirement_parse() {
+        assert_eq!(Requirement::parse("latest"), Requirement::Latest);
+        assert_eq!(Requirement::parse("LATEST"), Requirement::Latest);
+        assert_eq!(Requirement::parse("v1.2.3"), Requirement::Exact(version("1.2.3")));
+        assert_eq!(Requirement::parse("^14"), Requirement::Range(VersionReq::parse("^14").unwrap()));
+        assert_eq!(Requirement::parse(">=1.5,<2"), Requirement::Range(VersionReq::parse(">=1.5,<2").unwrap()));
+        assert_eq!(Requirement::parse("1.5"), Requirement::Range(VersionReq::parse("~1.5").unwrap()));
+        assert_eq!(Requirement::parse("nightly"), Requirement::Tag("nightly".to_string()));
+    }
+
+    #[test]
+    fn partial_versions_match_as_prefixes() {
+        let requirement = Requirement::parse("1.5");
+        assert!(requirement.matches("v1.5.7"));
+        assert!(!requirement.matches("v1.6.0"));
+
+        let requirement = Requirement::parse("14");
+        assert!(requirement.matches("14.1.0"));
+        assert!(!requirement.matches("15.0.0"));
+    }
+
+    #[test]
+    fn requirements_match_monorepo_tags() {
+        assert!(Requirement::parse("1.2.3").matches("h2-v1.2.3"));
+        assert!(Requirement::parse("^2").matches("s5cmd-v2.0.0"));
+        assert!(Requirement::parse("nightly").matches("nightly"));
+        assert!(!Requirement::parse("nightly").matches("nightly-2"));
+    }
+
+    #[test]
+    fn sort_releases_newest_first_with_non_versions_last() {
+        let mut releases: Vec<serde_json::Value> = ["nightly", "v1.9.0", "v1.10.0", "v1.10.0-rc.1"]
+            .iter()
+            .map(|tag| serde_json::json!({ "tag_name": tag }))
+            .collect();
+        sort_releases(&mut releases);
+
+        let tags: Vec<&str> = releases.iter().map(|r| r["tag_name"].as_str().unwrap()).collect();
+        assert_eq!(tags, ["v1.10.0", "v1.10.0-rc.1", "v1.9.0", "nightly"]);
+    }
+
+    #[test]
+    fn releases_are_narrowed_to_one_tool_of_a_monorepo() {
+        let mut releases: Vec<serde_json::Value> = ["sdk@3.1.0", "cli@1.9.0", "sdk@2.0.0", "cli@2.0.0-rc.1", "cli@1.10.0"]
+            .iter()
+            .map(|tag| serde_json::json!({ "tag_name": tag }))
+            .collect();
+
+        let mut cli = releases.clone();
+        retain_tool(&mut cli, Some("cli@"));
+        sort_releases(&mut cli);
+        let tags: Vec<&str> = cli.iter().map(|r| r["tag_name"].as_str().unwrap()).collect();
+        assert_eq!(tags, ["cli@2.0.0-rc.1", "cli@1.10.0", "cli@1.9.0"]);
+
+        let requirement = Requirement::parse("^2");
+        assert_eq!(cli.iter().find(|r| requirement.matches(r["tag_name"].as_str().unwrap())), None);
+        let requirement = Requirement::parse("1");
+        assert_eq!(cli.iter().find(|r| requirement.matches(r["tag_name"].as_str().unwrap()))
+            .map(|r| r["tag_name"].as_str().unwrap()), Some("cli@1.10.0"));
+
+        retain_tool(&mut releases, None);
+        assert_eq!(releases.len(), 5);
+        assert!(is_tool_tag("sdk@3.1.0", Some("sdk@")));
+        assert!(!is_tool_tag("sdk@3.1.0", Some("cli@")));
+    }
+}